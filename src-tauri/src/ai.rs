@@ -1,5 +1,13 @@
-use std::io;
-use std::process::{Command, Output, Stdio};
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{Child, ChildStderr, ChildStdout, Command, ExitStatus, Output, Stdio};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::{clamp_line_range, emit_dirty, save_file_state, settings, EditorState};
 
 #[cfg(windows)]
 const CREATE_NO_WINDOW: u32 = 0x08000000;
@@ -7,7 +15,7 @@ const CREATE_NO_WINDOW: u32 = 0x08000000;
 const DETACHED_PROCESS: u32 = 0x00000008;
 
 #[inline]
-fn configure_hidden(cmd: &mut Command) {
+pub fn configure_hidden(cmd: &mut Command) {
     cmd.stdin(Stdio::null())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped());
@@ -18,13 +26,66 @@ fn configure_hidden(cmd: &mut Command) {
     }
 }
 
+/// Running `ollama` processes by request id, so `ollama_cancel` can kill them.
+#[derive(Default)]
+/// A `None` slot is reserved for a request whose process is still being spawned.
+pub struct OllamaProcesses(Mutex<HashMap<String, Option<Child>>>);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AiStatusPayload {
+    request_id: Option<String>,
+    stage: &'static str,
+    detail: Option<String>,
+}
+
+fn emit_status(
+    app: &AppHandle,
+    request_id: &Option<String>,
+    stage: &'static str,
+    detail: Option<String>,
+) {
+    app.emit(
+        "ai-status",
+        AiStatusPayload {
+            request_id: request_id.clone(),
+            stage,
+            detail,
+        },
+    )
+    .map_err(|e| eprintln!("Failed to emit ai-status event: {}", e))
+    .ok();
+}
+
+fn finish_with_status<T>(
+    app: &AppHandle,
+    request_id: &Option<String>,
+    result: Result<T, String>,
+) -> Result<T, String> {
+    match &result {
+        Ok(_) => emit_status(app, request_id, "done", None),
+        Err(e) => emit_status(app, request_id, "error", Some(e.clone())),
+    }
+    result
+}
+
 #[tauri::command]
 pub fn ollama_available() -> bool {
     which::which("ollama").is_ok()
 }
 
 #[tauri::command]
-pub async fn ollama_model_is_downloaded(model: String) -> Result<bool, String> {
+pub async fn ollama_model_is_downloaded(
+    app: AppHandle,
+    model: String,
+    request_id: Option<String>,
+) -> Result<bool, String> {
+    emit_status(&app, &request_id, "checking", Some(model.clone()));
+    let result = model_is_downloaded(model).await;
+    finish_with_status(&app, &request_id, result)
+}
+
+async fn model_is_downloaded(model: String) -> Result<bool, String> {
     if !ollama_available() {
         return Err("ollama is not installed or not found in PATH".into());
     }
@@ -45,41 +106,207 @@ pub async fn ollama_model_is_downloaded(model: String) -> Result<bool, String> {
 }
 
 #[tauri::command]
-pub async fn ollama_pull_model(model: String) -> Result<String, String> {
+pub async fn ollama_pull_model(
+    app: AppHandle,
+    model: String,
+    request_id: Option<String>,
+) -> Result<String, String> {
+    emit_status(&app, &request_id, "checking", None);
     if !ollama_available() {
-        return Err("ollama is not installed or not found in PATH".into());
+        return finish_with_status(
+            &app,
+            &request_id,
+            Err("ollama is not installed or not found in PATH".into()),
+        );
     }
 
-    let output = run_ollama_async(vec!["pull".to_string(), model]).await?;
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        Err(if stderr.trim().is_empty() {
-            "ollama pull failed with unknown error".into()
-        } else {
-            stderr
-        })
-    }
+    emit_status(&app, &request_id, "pulling", Some(model.clone()));
+    let result = run_ollama_tracked(app.clone(), vec!["pull".to_string(), model], &request_id)
+        .await
+        .and_then(|output| {
+            if output.status.success() {
+                Ok(String::from_utf8_lossy(&output.stdout).to_string())
+            } else {
+                let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+                Err(if stderr.trim().is_empty() {
+                    "ollama pull failed with unknown error".into()
+                } else {
+                    stderr
+                })
+            }
+        });
+    finish_with_status(&app, &request_id, result)
 }
 
 #[tauri::command]
-pub async fn ollama_generate(model: String, prompt: String) -> Result<String, String> {
+pub async fn ollama_generate(
+    app: AppHandle,
+    model: String,
+    prompt: String,
+    request_id: Option<String>,
+) -> Result<String, String> {
+    emit_status(&app, &request_id, "checking", None);
     if !ollama_available() {
-        return Err("ollama is not installed or not found in PATH".into());
+        return finish_with_status(
+            &app,
+            &request_id,
+            Err("ollama is not installed or not found in PATH".into()),
+        );
     }
 
-    let output = run_ollama_async(vec!["run".to_string(), model, prompt]).await?;
-    if output.status.success() {
-        Ok(String::from_utf8_lossy(&output.stdout).to_string())
-    } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        Err(if stderr.trim().is_empty() {
-            "ollama run failed with unknown error".into()
+    emit_status(&app, &request_id, "generating", Some(model.clone()));
+    let result = run_ollama_tracked(
+        app.clone(),
+        vec!["run".to_string(), model, prompt],
+        &request_id,
+    )
+    .await
+    .and_then(|output| {
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
         } else {
-            stderr
-        })
+            let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+            Err(if stderr.trim().is_empty() {
+                "ollama run failed with unknown error".into()
+            } else {
+                stderr
+            })
+        }
+    });
+    finish_with_status(&app, &request_id, result)
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OllamaChunkPayload {
+    request_id: String,
+    text: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OllamaEndPayload {
+    request_id: String,
+    error: Option<String>,
+}
+
+// Emits each line of stdout as it arrives, then `ollama-done`, or `ollama-error` with
+// stderr when the process fails. Resolves once the process has exited.
+#[tauri::command]
+pub async fn ollama_generate_stream(
+    app: AppHandle,
+    model: String,
+    prompt: String,
+    request_id: String,
+) -> Result<(), String> {
+    let status_id = Some(request_id.clone());
+    emit_status(&app, &status_id, "checking", None);
+    if !ollama_available() {
+        return finish_with_status(
+            &app,
+            &status_id,
+            Err("ollama is not installed or not found in PATH".into()),
+        );
     }
+
+    emit_status(&app, &status_id, "generating", Some(model.clone()));
+    let worker = app.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let app = worker;
+        let id = Some(request_id.clone());
+        let args = ["run".to_string(), model, prompt];
+        let (child, stdout, stderr) = spawn_ollama(&app, &args, &id)?;
+        let stderr_reader = drain(stderr);
+
+        let mut reader = BufReader::new(stdout);
+        let mut line = Vec::new();
+        while reader
+            .read_until(b'\n', &mut line)
+            .map(|n| n > 0)
+            .unwrap_or(false)
+        {
+            app.emit(
+                "ollama-chunk",
+                OllamaChunkPayload {
+                    request_id: request_id.clone(),
+                    text: String::from_utf8_lossy(&line).to_string(),
+                },
+            )
+            .map_err(|e| eprintln!("Failed to emit ollama-chunk event: {}", e))
+            .ok();
+            line.clear();
+        }
+
+        let status = wait_ollama(&app, &id, child);
+        let stderr = String::from_utf8_lossy(&stderr_reader.join().unwrap_or_default()).to_string();
+        let (event, error) = match status {
+            Ok(status) if status.success() => ("ollama-done", None),
+            Ok(_) if stderr.trim().is_empty() => (
+                "ollama-error",
+                Some("ollama run failed with unknown error".to_string()),
+            ),
+            Ok(_) => ("ollama-error", Some(stderr)),
+            Err(e) => ("ollama-error", Some(e)),
+        };
+        app.emit(
+            event,
+            OllamaEndPayload {
+                request_id,
+                error: error.clone(),
+            },
+        )
+        .map_err(|e| eprintln!("Failed to emit {} event: {}", event, e))
+        .ok();
+        error.map_or(Ok(()), Err)
+    })
+    .await
+    .map_err(|e| e.to_string())
+    .and_then(|r| r);
+    finish_with_status(&app, &status_id, result)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiBufferContext {
+    path: String,
+    language: String,
+    content: String,
+    saved: bool,
+}
+
+#[tauri::command]
+pub fn ai_buffer_context(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    line_start: Option<usize>,
+    line_end: Option<usize>,
+) -> Result<AiBufferContext, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+
+    let mut saved = false;
+    if file.dirty && settings::get_bool("ai.save_before_context", false) {
+        save_file_state(file)?;
+        emit_dirty(&app, file);
+        saved = true;
+    }
+
+    let (start, end) = clamp_line_range(
+        file.lines.len(),
+        line_start.unwrap_or(0),
+        line_end.unwrap_or(usize::MAX),
+    );
+
+    Ok(AiBufferContext {
+        path: file.path.to_string_lossy().to_string(),
+        language: file.language.clone(),
+        content: file
+            .lines
+            .get(start..=end)
+            .map(|l| l.join("\n"))
+            .unwrap_or_default(),
+        saved,
+    })
 }
 
 fn run_ollama(args: &[&str]) -> io::Result<Output> {
@@ -89,13 +316,131 @@ fn run_ollama(args: &[&str]) -> io::Result<Output> {
     cmd.output()
 }
 
-async fn run_ollama_async(args: Vec<String>) -> Result<Output, String> {
+type Spawned = (Option<Child>, ChildStdout, ChildStderr);
+
+// With a request id the child is parked in `OllamaProcesses` and `None` is returned in its
+// place; `wait_ollama` takes it back out once the pipes are drained.
+fn spawn_ollama(
+    app: &AppHandle,
+    args: &[String],
+    request_id: &Option<String>,
+) -> Result<Spawned, String> {
+    let processes = app.state::<OllamaProcesses>();
+    if let Some(id) = request_id {
+        match processes.0.lock().unwrap().entry(id.clone()) {
+            Entry::Occupied(_) => return Err(format!("request {} is already running", id)),
+            Entry::Vacant(slot) => {
+                slot.insert(None);
+            }
+        }
+    }
+
+    let spawned = spawn_piped(args);
+    let id = match request_id {
+        Some(id) => id,
+        None => return spawned.map(|(child, stdout, stderr)| (Some(child), stdout, stderr)),
+    };
+    let mut guard = processes.0.lock().unwrap();
+    let (mut child, stdout, stderr) = match spawned {
+        Ok(v) => v,
+        Err(e) => {
+            guard.remove(id);
+            return Err(e);
+        }
+    };
+    // A cancel that arrived mid-spawn already took the reservation away.
+    match guard.get_mut(id) {
+        Some(slot) => {
+            *slot = Some(child);
+            Ok((None, stdout, stderr))
+        }
+        None => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err("request was cancelled".into())
+        }
+    }
+}
+
+fn spawn_piped(args: &[String]) -> Result<(Child, ChildStdout, ChildStderr), String> {
+    let mut cmd = Command::new("ollama");
+    cmd.args(args);
+    configure_hidden(&mut cmd);
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("failed to run ollama: {e}"))?;
+    match (child.stdout.take(), child.stderr.take()) {
+        (Some(stdout), Some(stderr)) => Ok((child, stdout, stderr)),
+        _ => {
+            let _ = child.kill();
+            let _ = child.wait();
+            Err("ollama output was not captured".into())
+        }
+    }
+}
+
+fn wait_ollama(
+    app: &AppHandle,
+    request_id: &Option<String>,
+    child: Option<Child>,
+) -> Result<ExitStatus, String> {
+    let child = child.or_else(|| {
+        let id = request_id.as_ref()?;
+        app.state::<OllamaProcesses>()
+            .0
+            .lock()
+            .unwrap()
+            .remove(id)
+            .flatten()
+    });
+    match child {
+        Some(mut c) => c.wait().map_err(|e| e.to_string()),
+        None => Err("request was cancelled".into()),
+    }
+}
+
+// Read on its own thread so a full stderr pipe can't stall the process while stdout is read.
+fn drain<R: Read + Send + 'static>(mut pipe: R) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+async fn run_ollama_tracked(
+    app: AppHandle,
+    args: Vec<String>,
+    request_id: &Option<String>,
+) -> Result<Output, String> {
+    let request_id = request_id.clone();
     tokio::task::spawn_blocking(move || {
-        let mut cmd = Command::new("ollama");
-        cmd.args(&args);
-        configure_hidden(&mut cmd);
-        cmd.output().map_err(|e| e.to_string())
+        let (child, mut stdout_pipe, stderr_pipe) = spawn_ollama(&app, &args, &request_id)?;
+        let stderr_reader = drain(stderr_pipe);
+        let mut stdout = Vec::new();
+        let _ = stdout_pipe.read_to_end(&mut stdout);
+        let status = wait_ollama(&app, &request_id, child)?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr: stderr_reader.join().unwrap_or_default(),
+        })
     })
     .await
     .map_err(|e| e.to_string())?
 }
+
+#[tauri::command]
+pub fn ollama_cancel(processes: State<'_, OllamaProcesses>, request_id: String) -> bool {
+    let child = processes.0.lock().unwrap().remove(&request_id);
+    match child {
+        Some(Some(mut c)) => {
+            let _ = c.kill();
+            let _ = c.wait();
+            true
+        }
+        // Still spawning; `spawn_ollama` kills the process once it finds the slot gone.
+        Some(None) => true,
+        None => false,
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{bookmarks, endings, EditorState, FileState};
+
+pub type AnchorId = u64;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnchorMove {
+    id: AnchorId,
+    /// `None` once the anchored line has been deleted.
+    line: Option<usize>,
+}
+
+#[derive(Default)]
+pub struct Anchors {
+    next_id: AnchorId,
+    lines: HashMap<AnchorId, usize>,
+    // Moves since the last `anchors-updated` event.
+    pending: Vec<AnchorMove>,
+}
+
+impl Anchors {
+    pub fn shift(&mut self, start: usize, inserted: usize, removed: usize) {
+        if inserted == removed {
+            return;
+        }
+        self.lines.retain(|&id, line| {
+            if *line < start {
+                true
+            } else if *line < start + removed {
+                self.pending.push(AnchorMove { id, line: None });
+                false
+            } else {
+                *line = *line - removed + inserted;
+                self.pending.push(AnchorMove {
+                    id,
+                    line: Some(*line),
+                });
+                true
+            }
+        });
+    }
+}
+
+// Keeps bookmarks, anchors and line breaks on their lines when the buffer gains or loses
+// lines.
+pub fn shift_lines(file: &mut FileState, start: usize, inserted: usize, removed: usize) {
+    bookmarks::shift_lines(&file.path, start, inserted, removed);
+    endings::shift_breaks(file, start, inserted, removed);
+    file.anchors.shift(start, inserted, removed);
+}
+
+pub fn emit_updates(app: &AppHandle, file: &mut FileState) {
+    if file.anchors.pending.is_empty() {
+        return;
+    }
+    let moves = std::mem::take(&mut file.anchors.pending);
+    app.emit("anchors-updated", &moves)
+        .map_err(|e| e.to_string())
+        .ok();
+}
+
+#[tauri::command]
+pub fn create_anchor(state: State<'_, EditorState>, line: usize) -> Result<AnchorId, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    if line >= file.lines.len() {
+        return Err(format!("line {} is out of range", line));
+    }
+    let id = file.anchors.next_id;
+    file.anchors.next_id += 1;
+    file.anchors.lines.insert(id, line);
+    Ok(id)
+}
+
+#[tauri::command]
+pub fn resolve_anchor(
+    state: State<'_, EditorState>,
+    id: AnchorId,
+) -> Result<Option<usize>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    Ok(file.anchors.lines.get(&id).copied())
+}
@@ -0,0 +1,133 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::State;
+
+use crate::{settings, EditorState};
+
+const DEFAULT_RETENTION_DAYS: u64 = 30;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeReport {
+    files: usize,
+    bytes: u64,
+}
+
+pub fn backups_dir() -> PathBuf {
+    settings::load_dir().join("backups")
+}
+
+// A path that no longer exists (the old side of a rename) resolves through its parent, so
+// it still produces the key it had while the file was there.
+fn canonical(path: &Path) -> PathBuf {
+    if let Ok(p) = path.canonicalize() {
+        return p;
+    }
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map(|p| p.join(name))
+            .unwrap_or_else(|_| path.to_path_buf()),
+        _ => path.to_path_buf(),
+    }
+}
+
+// Swap, autosave and backup files for a buffer all share this stem and differ by extension.
+// The key has to stay the same across releases, so it's a SHA-256 of the canonical path.
+pub fn backup_key(path: &Path) -> String {
+    let digest = Sha256::digest(canonical(path).to_string_lossy().as_bytes());
+    digest[..16].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// With `backup_on_save`, keeps the previous contents of `path` before a save replaces them.
+pub fn write_backup(path: &Path) {
+    if !settings::get_bool("backup_on_save", false) || !path.is_file() {
+        return;
+    }
+    let dir = backups_dir();
+    let target = dir.join(format!("{}.bak", backup_key(path)));
+    if let Err(e) = fs::create_dir_all(&dir).and_then(|_| fs::copy(path, &target)) {
+        eprintln!("Failed to back up {}: {}", path.display(), e);
+    }
+}
+
+#[tauri::command]
+pub fn purge_backups(
+    state: State<'_, EditorState>,
+    older_than_days: Option<u64>,
+) -> Result<PurgeReport, String> {
+    let days = older_than_days
+        .or_else(|| settings::get("backup_retention_days").and_then(|v| v.as_u64()))
+        .unwrap_or(DEFAULT_RETENTION_DAYS);
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(days.saturating_mul(24 * 60 * 60)))
+        .unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let protected: HashSet<String> = state
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|f| f.dirty)
+        .map(|f| backup_key(&f.path))
+        .collect();
+
+    let mut report = PurgeReport { files: 0, bytes: 0 };
+    let rd = match fs::read_dir(backups_dir()) {
+        Ok(v) => v,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(report),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    for entry in rd.flatten() {
+        let path = entry.path();
+        let meta = match entry.metadata() {
+            Ok(m) if m.is_file() => m,
+            _ => continue,
+        };
+        let stem = path.file_stem().map(|s| s.to_string_lossy().to_string());
+        if stem.is_some_and(|s| protected.contains(&s)) {
+            continue;
+        }
+        if meta.modified().map(|t| t >= cutoff).unwrap_or(true) {
+            continue;
+        }
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                report.files += 1;
+                report.bytes += meta.len();
+            }
+            Err(e) => eprintln!("Failed to purge {}: {}", path.display(), e),
+        }
+    }
+
+    Ok(report)
+}
+
+/// Carries a buffer's swap/autosave/backup files over when the file itself is renamed.
+pub fn migrate(old: &Path, new: &Path) {
+    let (old_key, new_key) = (backup_key(old), backup_key(new));
+    let dir = backups_dir();
+    let rd = match fs::read_dir(&dir) {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+    for entry in rd.flatten() {
+        let path = entry.path();
+        if path.file_stem().map(|s| s.to_string_lossy()) != Some(old_key.as_str().into()) {
+            continue;
+        }
+        let target = match path.extension() {
+            Some(ext) => dir.join(format!("{}.{}", new_key, ext.to_string_lossy())),
+            None => dir.join(&new_key),
+        };
+        if let Err(e) = fs::rename(&path, &target) {
+            eprintln!("Failed to migrate {}: {}", path.display(), e);
+        }
+    }
+}
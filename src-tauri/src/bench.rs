@@ -0,0 +1,75 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tree_sitter::Parser;
+
+use crate::{
+    collect_ts_tokens, detect_language_from_extension, get_ts_language, read_text_file,
+    resolve_path,
+};
+
+const MAX_BENCH_ITERATIONS: usize = 100;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchResult {
+    language: String,
+    iterations: usize,
+    bytes: usize,
+    token_count: usize,
+    min_ms: f64,
+    median_ms: f64,
+    max_ms: f64,
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
+
+fn run_benchmark(path: std::path::PathBuf, iterations: usize) -> Result<BenchResult, String> {
+    let text = read_text_file(&path)?;
+    let language = detect_language_from_extension(&path);
+    let lang = get_ts_language(&language)
+        .ok_or_else(|| format!("no grammar available for {}", language))?;
+    let mut parser = Parser::new();
+    parser.set_language(&lang).map_err(|e| e.to_string())?;
+
+    let mut durations = Vec::with_capacity(iterations);
+    let mut token_count = 0;
+    for _ in 0..iterations {
+        let started = Instant::now();
+        let tree = parser.parse(&text, None).ok_or("parse failed")?;
+        durations.push(started.elapsed());
+
+        let mut raw = Vec::new();
+        collect_ts_tokens(tree.root_node(), 0, usize::MAX, &mut raw);
+        token_count = raw.len();
+    }
+    durations.sort();
+
+    Ok(BenchResult {
+        language,
+        iterations,
+        bytes: text.len(),
+        token_count,
+        min_ms: millis(durations[0]),
+        median_ms: millis(durations[durations.len() / 2]),
+        max_ms: millis(durations[durations.len() - 1]),
+    })
+}
+
+// Parses a file on disk without touching the open buffer. Iterations are clamped to
+// 1..=MAX_BENCH_ITERATIONS.
+#[tauri::command]
+pub async fn benchmark_tokenization(
+    app: AppHandle,
+    path: String,
+    iterations: Option<usize>,
+) -> Result<BenchResult, String> {
+    let pb = resolve_path(&app, &path)?;
+    let iterations = iterations.unwrap_or(10).clamp(1, MAX_BENCH_ITERATIONS);
+    tokio::task::spawn_blocking(move || run_benchmark(pb, iterations))
+        .await
+        .map_err(|e| e.to_string())?
+}
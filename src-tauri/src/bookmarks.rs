@@ -0,0 +1,111 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use tauri::AppHandle;
+
+use crate::{resolve_typed_path, settings, write_atomic};
+
+type BookmarkMap = BTreeMap<String, BTreeSet<usize>>;
+
+static BOOKMARKS: Mutex<Option<BookmarkMap>> = Mutex::new(None);
+
+fn bookmarks_path() -> PathBuf {
+    settings::load_dir().join("bookmarks.json")
+}
+
+fn load_bookmarks() -> BookmarkMap {
+    fs::read_to_string(bookmarks_path())
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn persist(map: &BookmarkMap) -> Result<(), String> {
+    let path = bookmarks_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_string(map).map_err(|e| e.to_string())?;
+    write_atomic(&path, json.as_bytes())
+}
+
+fn with_bookmarks<T>(f: impl FnOnce(&mut BookmarkMap) -> T) -> T {
+    let mut guard = BOOKMARKS.lock().unwrap();
+    f(guard.get_or_insert_with(load_bookmarks))
+}
+
+// Keeps bookmarks attached to their lines when the open buffer gains or loses lines.
+pub fn shift_lines(path: &Path, start: usize, inserted: usize, removed: usize) {
+    let key = path.to_string_lossy().to_string();
+    with_bookmarks(|map| {
+        let lines = match map.get_mut(&key) {
+            Some(v) => v,
+            None => return,
+        };
+        let shifted: BTreeSet<usize> = lines
+            .iter()
+            .filter_map(|&line| {
+                if line < start {
+                    Some(line)
+                } else if line < start + removed {
+                    None
+                } else {
+                    Some(line - removed + inserted)
+                }
+            })
+            .collect();
+        if shifted == *lines {
+            return;
+        }
+        *lines = shifted;
+        if let Err(e) = persist(map) {
+            eprintln!("Failed to persist bookmarks: {}", e);
+        }
+    });
+}
+
+// Resolved the way `open_file` resolves its argument, so the key matches the buffer path
+// `shift_lines` is given.
+fn bookmark_key(app: &AppHandle, path: &str) -> Result<String, String> {
+    Ok(resolve_typed_path(app, path)?.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn toggle_bookmark(app: AppHandle, path: String, line: usize) -> Result<bool, String> {
+    let path = bookmark_key(&app, &path)?;
+    with_bookmarks(|map| {
+        let lines = map.entry(path.clone()).or_default();
+        let added = lines.insert(line);
+        if !added {
+            lines.remove(&line);
+        }
+        if lines.is_empty() {
+            map.remove(&path);
+        }
+        persist(map)?;
+        Ok(added)
+    })
+}
+
+#[tauri::command]
+pub fn list_bookmarks(app: AppHandle, path: String) -> Result<Vec<usize>, String> {
+    let path = bookmark_key(&app, &path)?;
+    Ok(with_bookmarks(|map| {
+        map.get(&path)
+            .map(|lines| lines.iter().copied().collect())
+            .unwrap_or_default()
+    }))
+}
+
+#[tauri::command]
+pub fn clear_bookmarks(app: AppHandle, path: String) -> Result<(), String> {
+    let path = bookmark_key(&app, &path)?;
+    with_bookmarks(|map| {
+        if map.remove(&path).is_some() {
+            persist(map)?;
+        }
+        Ok(())
+    })
+}
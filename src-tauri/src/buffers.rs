@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{
+    emit_buffer_state, load_file, resolve_path, workspace_ignores, EditorState, FileMetadata,
+    FileState,
+};
+
+/// Every open buffer keyed by path, in tab order, plus the one that buffer commands act on.
+#[derive(Default)]
+pub struct Buffers {
+    files: HashMap<PathBuf, FileState>,
+    order: Vec<PathBuf>,
+    active: Option<PathBuf>,
+}
+
+impl Buffers {
+    pub fn active(&self) -> Option<&FileState> {
+        self.files.get(self.active.as_ref()?)
+    }
+
+    pub fn active_mut(&mut self) -> Option<&mut FileState> {
+        let path = self.active.as_ref()?;
+        self.files.get_mut(path)
+    }
+
+    pub fn get_mut(&mut self, path: &Path) -> Option<&mut FileState> {
+        self.files.get_mut(path)
+    }
+
+    /// Open paths in tab order.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.order
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &FileState> {
+        self.order.iter().filter_map(|p| self.files.get(p))
+    }
+
+    /// Adds `file` as the active buffer, replacing any buffer already open at its path.
+    pub fn open(&mut self, file: FileState) {
+        let path = file.path.clone();
+        if self.files.insert(path.clone(), file).is_none() {
+            self.order.push(path.clone());
+        }
+        self.active = Some(path);
+    }
+
+    pub fn activate(&mut self, path: &Path) -> bool {
+        if !self.files.contains_key(path) {
+            return false;
+        }
+        self.active = Some(path.to_path_buf());
+        true
+    }
+
+    // Closing the active buffer activates the tab to its right, or to its left when it was last.
+    pub fn close(&mut self, path: &Path) -> Option<FileState> {
+        let file = self.files.remove(path)?;
+        let idx = self.order.iter().position(|p| p == path)?;
+        self.order.remove(idx);
+        if self.active.as_deref() == Some(path) {
+            self.active = self.order.get(idx).or_else(|| self.order.last()).cloned();
+        }
+        Some(file)
+    }
+
+    /// Moves the buffer at `from` to `to`, keeping its tab position and active state. A buffer
+    /// already open at `to` is closed, as its file has just been overwritten.
+    pub fn rekey(&mut self, from: &Path, to: PathBuf) {
+        if from == to {
+            return;
+        }
+        let mut file = match self.files.remove(from) {
+            Some(f) => f,
+            None => return,
+        };
+        if self.files.remove(&to).is_some() {
+            self.order.retain(|p| *p != to);
+            if self.active.as_ref() == Some(&to) {
+                self.active = Some(from.to_path_buf());
+            }
+        }
+        file.path = to.clone();
+        self.files.insert(to.clone(), file);
+        if let Some(slot) = self.order.iter_mut().find(|p| p.as_path() == from) {
+            *slot = to.clone();
+        }
+        if self.active.as_deref() == Some(from) {
+            self.active = Some(to);
+        }
+    }
+}
+
+pub fn metadata(app: &AppHandle, file: &FileState) -> FileMetadata {
+    FileMetadata {
+        name: file.name.clone(),
+        path: file.path.to_string_lossy().to_string(),
+        size: file.size,
+        disk_size: file.disk_size,
+        language: file.language.clone(),
+        line_count: file.lines.len(),
+        lossy: file.lossy,
+        ignored: workspace_ignores(app, &file.path),
+        line_ending: file.line_ending,
+        mixed_line_endings: file.ending_counts.is_mixed(),
+        trailing_newline: file.lines.len() > 1 && file.lines.last().is_some_and(|l| l.is_empty()),
+    }
+}
+
+// Emits `file-opened` like `open_file` does, so the editor reloads its view the same way.
+#[tauri::command]
+pub fn switch_buffer(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    path: String,
+) -> Result<FileMetadata, String> {
+    let pb = resolve_path(&app, &path)?;
+    let mut guard = state.0.lock().unwrap();
+    if !guard.activate(&pb) {
+        return Err("file is not open".into());
+    }
+    let file = guard.active_mut().ok_or("file is not open")?;
+    let meta = metadata(&app, file);
+    app.emit("file-opened", &meta)
+        .map_err(|e| e.to_string())
+        .ok();
+    emit_buffer_state(&app, file);
+    Ok(meta)
+}
+
+// Re-reads the active buffer from disk. Unsaved edits are only thrown away with `discard`.
+#[tauri::command]
+pub fn reload_buffer(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    discard: Option<bool>,
+) -> Result<FileMetadata, String> {
+    let mut guard = state.0.lock().unwrap();
+    let current = guard.active().ok_or("no file opened")?;
+    if current.dirty && !discard.unwrap_or(false) {
+        return Err("buffer has unsaved changes".into());
+    }
+    let (meta, file) = load_file(&app, current.path.clone())?;
+    guard.open(file);
+    app.emit("file-opened", &meta)
+        .map_err(|e| e.to_string())
+        .ok();
+    if let Some(file) = guard.active_mut() {
+        emit_buffer_state(&app, file);
+    }
+    Ok(meta)
+}
+
+#[tauri::command]
+pub fn list_open_buffers(app: AppHandle, state: State<'_, EditorState>) -> Vec<FileMetadata> {
+    let guard = state.0.lock().unwrap();
+    guard.iter().map(|f| metadata(&app, f)).collect()
+}
@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices, group_diff_ops, Algorithm, DiffTag};
+use tauri::{AppHandle, State};
+
+use crate::{
+    dirhash, is_binary_content, read_text_file, resolve_path, split_lines, EditorState, FileState,
+};
+
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffOptions {
+    #[serde(default)]
+    pub ignore_whitespace: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    kind: String,
+    content: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    old_start: usize,
+    old_lines: usize,
+    new_start: usize,
+    new_lines: usize,
+    lines: Vec<DiffLine>,
+}
+
+fn normalize_line(line: &str, opts: &DiffOptions) -> String {
+    if opts.ignore_whitespace {
+        line.split_whitespace().collect::<Vec<_>>().join(" ")
+    } else {
+        line.to_string()
+    }
+}
+
+pub fn diff_lines(old: &[String], new: &[String], opts: &DiffOptions) -> Vec<DiffHunk> {
+    let old_keys: Vec<String> = old.iter().map(|l| normalize_line(l, opts)).collect();
+    let new_keys: Vec<String> = new.iter().map(|l| normalize_line(l, opts)).collect();
+    if old_keys == new_keys {
+        return Vec::new();
+    }
+
+    let ops = capture_diff_slices(Algorithm::Myers, &old_keys, &new_keys);
+    let mut hunks = Vec::new();
+
+    for group in group_diff_ops(ops, CONTEXT_LINES) {
+        let (first, last) = match (group.first(), group.last()) {
+            (Some(f), Some(l)) => (f, l),
+            _ => continue,
+        };
+        let old_start = first.old_range().start;
+        let new_start = first.new_range().start;
+        let mut hunk = DiffHunk {
+            old_start,
+            old_lines: last.old_range().end - old_start,
+            new_start,
+            new_lines: last.new_range().end - new_start,
+            lines: Vec::new(),
+        };
+
+        for op in &group {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            match tag {
+                DiffTag::Equal => {
+                    for i in old_range {
+                        hunk.lines.push(DiffLine {
+                            kind: "equal".into(),
+                            content: old[i].clone(),
+                        });
+                    }
+                }
+                DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                    for i in old_range {
+                        hunk.lines.push(DiffLine {
+                            kind: "delete".into(),
+                            content: old[i].clone(),
+                        });
+                    }
+                    for i in new_range {
+                        hunk.lines.push(DiffLine {
+                            kind: "insert".into(),
+                            content: new[i].clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        hunks.push(hunk);
+    }
+
+    hunks
+}
+
+#[tauri::command]
+pub fn diff_files(
+    app: AppHandle,
+    a: String,
+    b: String,
+    opts: Option<DiffOptions>,
+) -> Result<Vec<DiffHunk>, String> {
+    let opts = opts.unwrap_or_default();
+    let old =
+        split_lines(&read_text_file(&resolve_path(&app, &a)?).map_err(|e| format!("{a}: {e}"))?);
+    let new =
+        split_lines(&read_text_file(&resolve_path(&app, &b)?).map_err(|e| format!("{b}: {e}"))?);
+    Ok(diff_lines(&old, &new, &opts))
+}
+
+#[tauri::command]
+pub fn diff_with_text(
+    state: State<'_, EditorState>,
+    other: String,
+    opts: Option<DiffOptions>,
+) -> Result<Vec<DiffHunk>, String> {
+    let opts = opts.unwrap_or_default();
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    Ok(diff_lines(&file.lines, &split_lines(&other), &opts))
+}
+
+pub fn disk_lines(file: &FileState) -> Result<Vec<String>, String> {
+    Ok(split_lines(&read_text_file(&file.path)?))
+}
+
+// Runs of whitespace count as one separator, so "a b" and "ab" still differ.
+#[tauri::command]
+pub fn only_whitespace_changed(state: State<'_, EditorState>) -> Result<bool, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let on_disk = disk_lines(file)?;
+    let opts = DiffOptions {
+        ignore_whitespace: true,
+    };
+    Ok(diff_lines(&on_disk, &file.lines, &opts).is_empty())
+}
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSummary {
+    added: usize,
+    removed: usize,
+    modified: usize,
+    dirty: bool,
+}
+
+// Replaced runs count as modified up to the shorter side; the rest are adds or removes.
+#[tauri::command]
+pub fn pending_changes_summary(state: State<'_, EditorState>) -> Result<ChangeSummary, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let mut summary = ChangeSummary {
+        dirty: file.dirty,
+        ..Default::default()
+    };
+    if !file.dirty {
+        return Ok(summary);
+    }
+
+    let on_disk = disk_lines(file)?;
+    for op in capture_diff_slices(Algorithm::Myers, &on_disk, &file.lines) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        let (old_len, new_len) = (old_range.len(), new_range.len());
+        match tag {
+            DiffTag::Equal => {}
+            DiffTag::Delete => summary.removed += old_len,
+            DiffTag::Insert => summary.added += new_len,
+            DiffTag::Replace => {
+                let common = old_len.min(new_len);
+                summary.modified += common;
+                summary.removed += old_len - common;
+                summary.added += new_len - common;
+            }
+        }
+    }
+    Ok(summary)
+}
+
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DirDiffStatus {
+    OnlyInA,
+    OnlyInB,
+    Modified,
+    Identical,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirDiffEntry {
+    /// Relative to both roots, with `/` separators.
+    path: String,
+    status: DirDiffStatus,
+}
+
+fn relative_files(root: &Path) -> BTreeSet<String> {
+    dirhash::collect_files(root)
+        .iter()
+        .filter_map(|p| p.strip_prefix(root).ok())
+        .map(|p| p.to_string_lossy().replace('\\', "/"))
+        .collect()
+}
+
+// Byte-identical files short-circuit; binaries are only ever compared byte for byte.
+fn compare_files(a: &Path, b: &Path, opts: &DiffOptions) -> DirDiffStatus {
+    if let (Ok(meta_a), Ok(meta_b)) = (fs::metadata(a), fs::metadata(b)) {
+        if meta_a.len() != meta_b.len() && !opts.ignore_whitespace {
+            return DirDiffStatus::Modified;
+        }
+    }
+    let (bytes_a, bytes_b) = match (fs::read(a), fs::read(b)) {
+        (Ok(x), Ok(y)) => (x, y),
+        _ => return DirDiffStatus::Modified,
+    };
+    if bytes_a == bytes_b {
+        return DirDiffStatus::Identical;
+    }
+    if !opts.ignore_whitespace || is_binary_content(&bytes_a) || is_binary_content(&bytes_b) {
+        return DirDiffStatus::Modified;
+    }
+    match (String::from_utf8(bytes_a), String::from_utf8(bytes_b)) {
+        (Ok(x), Ok(y)) if diff_lines(&split_lines(&x), &split_lines(&y), opts).is_empty() => {
+            DirDiffStatus::Identical
+        }
+        _ => DirDiffStatus::Modified,
+    }
+}
+
+#[tauri::command]
+pub async fn diff_directories(
+    app: AppHandle,
+    a: String,
+    b: String,
+    opts: Option<DiffOptions>,
+) -> Result<Vec<DirDiffEntry>, String> {
+    let root_a = resolve_path(&app, &a)?;
+    let root_b = resolve_path(&app, &b)?;
+    if !root_a.is_dir() || !root_b.is_dir() {
+        return Err("both paths must be directories".into());
+    }
+    let opts = opts.unwrap_or_default();
+
+    tokio::task::spawn_blocking(move || {
+        let (files_a, files_b) =
+            rayon::join(|| relative_files(&root_a), || relative_files(&root_b));
+        let all: Vec<&String> = files_a.union(&files_b).collect();
+        all.par_iter()
+            .map(|path| {
+                let status = match (files_a.contains(*path), files_b.contains(*path)) {
+                    (true, false) => DirDiffStatus::OnlyInA,
+                    (false, true) => DirDiffStatus::OnlyInB,
+                    _ => compare_files(&root_a.join(path), &root_b.join(path), &opts),
+                };
+                DirDiffEntry {
+                    path: path.to_string(),
+                    status,
+                }
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
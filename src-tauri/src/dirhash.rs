@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use rayon::prelude::*;
+use tauri::AppHandle;
+
+use crate::identity::content_hash;
+use crate::{is_binary_content, resolve_path, walk_files};
+
+/// Binary files larger than this are left out of the map.
+const MAX_BINARY_BYTES: u64 = 8 * 1024 * 1024;
+
+// Per root, the (mtime, length, hash) of every file seen, so unchanged files are not re-read.
+type HashCache = HashMap<PathBuf, HashMap<String, (SystemTime, u64, String)>>;
+static HASH_CACHE: Mutex<Option<HashCache>> = Mutex::new(None);
+
+pub fn collect_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    walk_files(root, |path| {
+        files.push(path.to_path_buf());
+        true
+    });
+    files
+}
+
+fn is_large_binary(path: &Path, len: u64) -> bool {
+    if len <= MAX_BINARY_BYTES {
+        return false;
+    }
+    let mut head = Vec::new();
+    File::open(path)
+        .and_then(|f| f.take(8000).read_to_end(&mut head))
+        .map(|_| is_binary_content(&head))
+        .unwrap_or(true)
+}
+
+fn relative_key(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+fn hash_tree(root: &Path) -> HashMap<String, String> {
+    let previous = HASH_CACHE
+        .lock()
+        .unwrap()
+        .as_mut()
+        .and_then(|c| c.remove(root))
+        .unwrap_or_default();
+
+    let entries: HashMap<String, (SystemTime, u64, String)> = collect_files(root)
+        .par_iter()
+        .filter_map(|path| {
+            let meta = fs::metadata(path).ok()?;
+            let mtime = meta.modified().ok()?;
+            let key = relative_key(root, path);
+            if let Some(cached) = previous.get(&key) {
+                if cached.0 == mtime && cached.1 == meta.len() {
+                    return Some((key, cached.clone()));
+                }
+            }
+            if is_large_binary(path, meta.len()) {
+                return None;
+            }
+            let hash = content_hash(&fs::read(path).ok()?);
+            Some((key, (mtime, meta.len(), hash)))
+        })
+        .collect();
+
+    let hashes = entries
+        .iter()
+        .map(|(k, (_, _, h))| (k.clone(), h.clone()))
+        .collect();
+    HASH_CACHE
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(root.to_path_buf(), entries);
+    hashes
+}
+
+async fn hash_root(app: &AppHandle, root: &str) -> Result<HashMap<String, String>, String> {
+    let root_pb = resolve_path(app, root)?;
+    if !root_pb.is_dir() {
+        return Err("path is not a directory".into());
+    }
+    tokio::task::spawn_blocking(move || hash_tree(&root_pb))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Maps each file's path relative to `root` (with `/` separators) to its content hash.
+#[tauri::command]
+pub async fn hash_directory(
+    app: AppHandle,
+    root: String,
+) -> Result<HashMap<String, String>, String> {
+    hash_root(&app, &root).await
+}
+
+// Added, modified and removed files all count as changed.
+#[tauri::command]
+pub async fn changed_since(
+    app: AppHandle,
+    root: String,
+    previous_hashes: HashMap<String, String>,
+) -> Result<Vec<String>, String> {
+    let current = hash_root(&app, &root).await?;
+    let mut changed: Vec<String> = current
+        .iter()
+        .filter(|(path, hash)| previous_hashes.get(*path) != Some(*hash))
+        .map(|(path, _)| path.clone())
+        .chain(
+            previous_hashes
+                .keys()
+                .filter(|path| !current.contains_key(*path))
+                .cloned(),
+        )
+        .collect();
+    changed.sort();
+    Ok(changed)
+}
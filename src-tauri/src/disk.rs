@@ -0,0 +1,66 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::resolve_path;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskSpace {
+    total: u64,
+    available: u64,
+}
+
+// Paths that don't exist yet (a save or copy destination) are measured on their nearest existing ancestor.
+fn nearest_existing(path: &Path) -> Option<PathBuf> {
+    let mut current = Some(path);
+    while let Some(p) = current {
+        if p.exists() {
+            return Some(p.to_path_buf());
+        }
+        current = p.parent();
+    }
+    None
+}
+
+fn query_space(path: &Path) -> Result<DiskSpace, String> {
+    let existing = nearest_existing(path).ok_or("no existing ancestor for path")?;
+    Ok(DiskSpace {
+        total: fs2::total_space(&existing).map_err(|e| e.to_string())?,
+        available: fs2::available_space(&existing).map_err(|e| e.to_string())?,
+    })
+}
+
+// Symlinks count as themselves and are never followed, so a link loop or a link to `/`
+// can't make the walk run away.
+pub fn path_size(path: &Path) -> u64 {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(m) => m,
+        Err(_) => return 0,
+    };
+    if meta.is_dir() {
+        fs::read_dir(path)
+            .map(|rd| rd.flatten().map(|e| path_size(&e.path())).sum())
+            .unwrap_or(0)
+    } else {
+        meta.len()
+    }
+}
+
+pub fn ensure_space(dest: &Path, needed: u64) -> Result<(), String> {
+    let space = query_space(dest)?;
+    if space.available < needed {
+        return Err(format!(
+            "insufficient space: need {} bytes, {} available",
+            needed, space.available
+        ));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn disk_free_space(app: AppHandle, path: String) -> Result<DiskSpace, String> {
+    query_space(&resolve_path(&app, &path)?)
+}
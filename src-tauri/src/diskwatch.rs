@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::EditorState;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ChangedOnDiskPayload {
+    path: String,
+    deleted: bool,
+}
+
+/// The open file's mtime as of the last open or save, plus a watcher that reports
+/// writes made by other programs. Dropping it stops the watcher, so replacing or
+/// closing the buffer needs no extra cleanup.
+#[derive(Default)]
+pub struct DiskWatch {
+    recorded: Arc<Mutex<Option<SystemTime>>>,
+    _watcher: Option<RecommendedWatcher>,
+}
+
+fn mtime(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).ok()?.modified().ok()
+}
+
+// A file that appeared or disappeared since it was recorded counts as changed too.
+fn is_newer(now: Option<SystemTime>, then: Option<SystemTime>) -> bool {
+    match (now, then) {
+        (Some(now), Some(then)) => now > then,
+        (now, then) => now.is_some() != then.is_some(),
+    }
+}
+
+impl DiskWatch {
+    pub fn start(app: &AppHandle, path: &Path) -> Self {
+        let recorded = Arc::new(Mutex::new(mtime(path)));
+        DiskWatch {
+            _watcher: spawn_watcher(app.clone(), path.to_path_buf(), recorded.clone()),
+            recorded,
+        }
+    }
+
+    /// Runs a write made by the backend itself and records the resulting mtime. The lock
+    /// is held throughout so the watcher can't report the write as an external change.
+    pub fn guard_write<T>(
+        &self,
+        path: &Path,
+        write: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut recorded = self.recorded.lock().unwrap();
+        let result = write();
+        *recorded = mtime(path);
+        result
+    }
+
+    /// Takes the file as it is now as the backend's own, for when it already matches the
+    /// buffer and no write was needed.
+    pub fn record(&self, path: &Path) {
+        *self.recorded.lock().unwrap() = mtime(path);
+    }
+
+    fn changed(&self, path: &Path) -> bool {
+        is_newer(mtime(path), *self.recorded.lock().unwrap())
+    }
+}
+
+// Watching the directory keeps events flowing when another editor saves by
+// renaming a temp file over the original.
+fn spawn_watcher(
+    app: AppHandle,
+    path: PathBuf,
+    recorded: Arc<Mutex<Option<SystemTime>>>,
+) -> Option<RecommendedWatcher> {
+    let name = path.file_name()?.to_os_string();
+    let dir = path.parent()?.to_path_buf();
+    let watched = path.clone();
+    let mut last_reported = None;
+    let mut watcher =
+        match notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let event = match res {
+                Ok(e) => e,
+                Err(_) => return,
+            };
+            if !event.paths.iter().any(|p| p.file_name() == Some(&name)) {
+                return;
+            }
+            let now = mtime(&watched);
+            // A single save usually produces several events; report each new mtime once.
+            if !is_newer(now, *recorded.lock().unwrap()) || last_reported == Some(now) {
+                return;
+            }
+            last_reported = Some(now);
+            app.emit(
+                "file-changed-on-disk",
+                ChangedOnDiskPayload {
+                    path: watched.to_string_lossy().to_string(),
+                    deleted: now.is_none(),
+                },
+            )
+            .map_err(|e| e.to_string())
+            .ok();
+        }) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("Failed to create file watcher: {}", e);
+                return None;
+            }
+        };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        eprintln!("Failed to watch {}: {}", dir.display(), e);
+        return None;
+    }
+    Some(watcher)
+}
+
+#[tauri::command]
+pub fn check_external_modification(state: State<'_, EditorState>) -> Result<bool, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    Ok(file.disk.changed(&file.path))
+}
@@ -0,0 +1,305 @@
+use serde::Deserialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::history::{self, LineSplice};
+use crate::{
+    anchors, buffer_language, buffers, emit_dirty, endings, mark_modified, resolve_in_workspace,
+    write_atomic, EditorState, FileMetadata, FileState, Offset,
+};
+
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EditOp {
+    start: Offset,
+    end: Offset,
+    text: String,
+}
+
+fn check_offset(lines: &[String], offset: &Offset) -> Result<(), String> {
+    let line = lines
+        .get(offset.row)
+        .ok_or_else(|| format!("row {} is out of range", offset.row))?;
+    if offset.col > line.len() || !line.is_char_boundary(offset.col) {
+        return Err(format!(
+            "column {} is not a valid position on row {}",
+            offset.col, offset.row
+        ));
+    }
+    Ok(())
+}
+
+// Replaces the text between `start` and `end` with `text`, returning the recorded
+// splice and the offset just past the inserted text.
+fn splice_text(
+    lines: &mut Vec<String>,
+    start: &Offset,
+    end: &Offset,
+    text: &str,
+) -> Result<(LineSplice, Offset), String> {
+    check_offset(lines, start)?;
+    check_offset(lines, end)?;
+    if (end.row, end.col) < (start.row, start.col) {
+        return Err("edit range end is before its start".into());
+    }
+
+    let prefix = &lines[start.row][..start.col];
+    let suffix = &lines[end.row][end.col..];
+    let pieces: Vec<&str> = text.split('\n').map(|s| s.trim_end_matches('\r')).collect();
+
+    let mut new_lines: Vec<String> = Vec::with_capacity(pieces.len());
+    for (i, piece) in pieces.iter().enumerate() {
+        let mut line = String::new();
+        if i == 0 {
+            line.push_str(prefix);
+        }
+        line.push_str(piece);
+        new_lines.push(line);
+    }
+    let last_piece_len = new_lines.last().map(|l| l.len()).unwrap_or(0);
+    if let Some(last) = new_lines.last_mut() {
+        last.push_str(suffix);
+    }
+
+    let cursor = Offset {
+        row: start.row + new_lines.len() - 1,
+        col: last_piece_len,
+    };
+
+    let old: Vec<String> = lines
+        .splice(start.row..=end.row, new_lines.iter().cloned())
+        .collect();
+
+    Ok((
+        LineSplice {
+            start: start.row,
+            old,
+            new: new_lines,
+        },
+        cursor,
+    ))
+}
+
+fn shift_lines(file: &mut FileState, splice: &LineSplice) {
+    if splice.old.len() != splice.new.len() {
+        anchors::shift_lines(
+            file,
+            splice.start + 1,
+            splice.new.len().saturating_sub(1),
+            splice.old.len().saturating_sub(1),
+        );
+    }
+}
+
+pub fn replace_range(
+    file: &mut FileState,
+    start: &Offset,
+    end: &Offset,
+    text: &str,
+) -> Result<(LineSplice, Offset), String> {
+    let (splice, cursor) = splice_text(&mut file.lines, start, end, text)?;
+    shift_lines(file, &splice);
+    mark_modified(file);
+    Ok((splice, cursor))
+}
+
+// Emits updates for rows `start..end` of the previous layout. When the line count
+// changed, every row from `start` on has moved, so all of them are re-sent.
+pub fn emit_changed(
+    app: &AppHandle,
+    file: &mut FileState,
+    start: usize,
+    end: usize,
+    old_total: usize,
+) {
+    let total = file.lines.len();
+    let last = if total == old_total {
+        end
+    } else {
+        let (kind, count) = if total > old_total {
+            ("insert", total - old_total)
+        } else {
+            ("remove", old_total - total)
+        };
+        app.emit(
+            "file-structure-changed",
+            serde_json::json!({
+              "kind": kind,
+              "start": start,
+              "count": count,
+              "totalLines": total
+            }),
+        )
+        .map_err(|e| e.to_string())
+        .ok();
+        total
+    };
+
+    for i in start..last.min(total) {
+        app.emit(
+            "file-updated",
+            serde_json::json!({
+              "line": i,
+              "content": file.lines[i],
+              "totalLines": file.lines.len()
+            }),
+        )
+        .map_err(|e| e.to_string())
+        .ok();
+    }
+    emit_dirty(app, file);
+    anchors::emit_updates(app, file);
+}
+
+#[tauri::command]
+pub fn insert_text(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    offset: Offset,
+    text: String,
+) -> Result<Offset, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+
+    let old_total = file.lines.len();
+    let (splice, cursor) = replace_range(file, &offset, &offset, &text)?;
+    emit_changed(
+        &app,
+        file,
+        splice.start,
+        splice.start + splice.new.len(),
+        old_total,
+    );
+    file.history.push_coalescing(splice);
+    history::emit_availability(&app, &mut file.history);
+    Ok(cursor)
+}
+
+#[tauri::command]
+pub fn apply_edit(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    start_row: usize,
+    start_col: usize,
+    end_row: usize,
+    end_col: usize,
+    text: String,
+) -> Result<Offset, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+
+    let start = Offset {
+        row: start_row,
+        col: start_col,
+    };
+    let end = Offset {
+        row: end_row,
+        col: end_col,
+    };
+    let old_total = file.lines.len();
+    let (splice, cursor) = replace_range(file, &start, &end, &text)?;
+    emit_changed(
+        &app,
+        file,
+        splice.start,
+        splice.start + splice.new.len().max(splice.old.len()),
+        old_total,
+    );
+    file.history.push_coalescing(splice);
+    history::emit_availability(&app, &mut file.history);
+    Ok(cursor)
+}
+
+// Applies every op or none: the ops run against a copy of the buffer, which only
+// replaces the real one once all of them have succeeded.
+#[tauri::command]
+pub fn apply_transaction(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    ops: Vec<EditOp>,
+) -> Result<(), String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    if ops.is_empty() {
+        return Ok(());
+    }
+
+    let mut lines = file.lines.clone();
+    let mut group = Vec::with_capacity(ops.len());
+    for (i, op) in ops.iter().enumerate() {
+        let (splice, _) = splice_text(&mut lines, &op.start, &op.end, &op.text)
+            .map_err(|e| format!("op {} failed: {}", i, e))?;
+        group.push(splice);
+    }
+
+    let old_total = file.lines.len();
+    let first = group.iter().map(|s| s.start).min().unwrap_or(0);
+    let end = group
+        .iter()
+        .map(|s| s.start + s.new.len().max(s.old.len()))
+        .max()
+        .unwrap_or(first);
+
+    file.lines = lines;
+    for splice in &group {
+        shift_lines(file, splice);
+    }
+    mark_modified(file);
+    emit_changed(&app, file, first, end, old_total);
+    file.history.push(group);
+    history::emit_availability(&app, &mut file.history);
+    Ok(())
+}
+
+// The new file is written before the buffer is touched, so a failed write leaves the
+// buffer as it was. The line break ending `row - 1` goes with the moved lines.
+#[tauri::command]
+pub fn split_buffer_at(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    row: usize,
+    dest: String,
+) -> Result<FileMetadata, String> {
+    let dest_pb = resolve_in_workspace(&app, &dest)?;
+    if dest_pb.exists() {
+        return Err("destination already exists".into());
+    }
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    if row >= file.lines.len() {
+        return Err(format!("row {} is out of range", row));
+    }
+
+    let breaks = endings::line_breaks(file);
+    let mut moved = String::new();
+    for (i, line) in file.lines.iter().enumerate().skip(row) {
+        moved.push_str(line);
+        if let Some(ending) = breaks.get(i) {
+            moved.push_str(ending.as_str());
+        }
+    }
+    write_atomic(&dest_pb, moved.as_bytes())?;
+    let language = buffer_language(&dest_pb, &moved);
+    let meta = buffers::metadata(
+        &app,
+        &FileState::from_text(dest_pb, &moved, language, false),
+    );
+
+    let last = file.lines.len() - 1;
+    let start = match row {
+        0 => Offset { row: 0, col: 0 },
+        _ => Offset {
+            row: row - 1,
+            col: file.lines[row - 1].len(),
+        },
+    };
+    let end = Offset {
+        row: last,
+        col: file.lines[last].len(),
+    };
+    let old_total = file.lines.len();
+    let (splice, _) = replace_range(file, &start, &end, "")?;
+    emit_changed(&app, file, splice.start, old_total, old_total);
+    file.history.push(vec![splice]);
+    history::emit_availability(&app, &mut file.history);
+    Ok(meta)
+}
@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{emit_dirty, history, mark_modified, EditorState, FileState};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+    Cr,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+            LineEnding::Cr => "\r",
+        }
+    }
+}
+
+/// How many line breaks of each style the file had when it was last read or saved.
+#[derive(Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndingCounts {
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+}
+
+// Every line break in `text`, in order, split the same way as `split_lines`.
+pub fn breaks_of(text: &str) -> Vec<LineEnding> {
+    let mut breaks = Vec::new();
+    let mut bytes = text.bytes().peekable();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\n' => breaks.push(LineEnding::Lf),
+            b'\r' if bytes.peek() == Some(&b'\n') => {
+                bytes.next();
+                breaks.push(LineEnding::Crlf);
+            }
+            b'\r' => breaks.push(LineEnding::Cr),
+            _ => {}
+        }
+    }
+    breaks
+}
+
+impl EndingCounts {
+    pub fn of(text: &str) -> Self {
+        Self::tally(&breaks_of(text))
+    }
+
+    pub fn tally(breaks: &[LineEnding]) -> Self {
+        let mut counts = EndingCounts::default();
+        for &ending in breaks {
+            *counts.get_mut(ending) += 1;
+        }
+        counts
+    }
+
+    pub fn uniform(ending: LineEnding, breaks: usize) -> Self {
+        let mut counts = EndingCounts::default();
+        *counts.get_mut(ending) = breaks;
+        counts
+    }
+
+    fn get_mut(&mut self, ending: LineEnding) -> &mut usize {
+        match ending {
+            LineEnding::Lf => &mut self.lf,
+            LineEnding::Crlf => &mut self.crlf,
+            LineEnding::Cr => &mut self.cr,
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.lf + self.crlf + self.cr
+    }
+
+    // Ties and files without any line break default to LF.
+    pub fn dominant(&self) -> LineEnding {
+        if self.crlf > self.lf && self.crlf >= self.cr {
+            LineEnding::Crlf
+        } else if self.cr > self.lf && self.cr > self.crlf {
+            LineEnding::Cr
+        } else {
+            LineEnding::Lf
+        }
+    }
+
+    pub fn is_mixed(&self) -> bool {
+        [self.lf, self.crlf, self.cr]
+            .iter()
+            .filter(|&&n| n > 0)
+            .count()
+            > 1
+    }
+}
+
+/// Keeps `file.breaks` in step when lines `start..start + removed` are replaced by
+/// `inserted` new ones. Each line takes or gives up the break in front of it (the first
+/// line, which has none, the one after it), and new breaks use `line_ending`.
+pub fn shift_breaks(file: &mut FileState, start: usize, inserted: usize, removed: usize) {
+    if inserted == removed {
+        return;
+    }
+    let at = start.saturating_sub(1).min(file.breaks.len());
+    let end = (at + removed).min(file.breaks.len());
+    file.breaks
+        .splice(at..end, std::iter::repeat_n(file.line_ending, inserted));
+}
+
+/// The break that follows each line but the last when the buffer is saved.
+pub fn line_breaks(file: &FileState) -> Vec<LineEnding> {
+    let mut breaks = file.breaks.clone();
+    breaks.resize(file.lines.len().saturating_sub(1), file.line_ending);
+    breaks
+}
+
+/// The buffer as it would be written to disk, with its break counts.
+pub fn saved_text(file: &FileState) -> (String, EndingCounts) {
+    let breaks = line_breaks(file);
+    let mut text = String::with_capacity(file.size + breaks.len());
+    for (i, line) in file.lines.iter().enumerate() {
+        text.push_str(line);
+        if let Some(ending) = breaks.get(i) {
+            text.push_str(ending.as_str());
+        }
+    }
+    (text, EndingCounts::tally(&breaks))
+}
+
+#[derive(Clone)]
+pub struct LineEndingChange {
+    pub old: LineEnding,
+    pub old_counts: EndingCounts,
+    pub old_breaks: Vec<LineEnding>,
+    pub new: LineEnding,
+}
+
+pub fn emit_line_ending(app: &AppHandle, ending: LineEnding) {
+    app.emit(
+        "line-ending-changed",
+        serde_json::json!({ "lineEnding": ending }),
+    )
+    .map_err(|e| e.to_string())
+    .ok();
+}
+
+#[tauri::command]
+pub fn normalize_line_endings(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    target: LineEnding,
+) -> Result<usize, String> {
+    convert(&app, &state, target)
+}
+
+/// Same conversion as `normalize_line_endings`, named for the status-bar picker.
+#[tauri::command]
+pub fn set_line_ending(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    kind: LineEnding,
+) -> Result<usize, String> {
+    convert(&app, &state, kind)
+}
+
+fn convert(app: &AppHandle, state: &EditorState, target: LineEnding) -> Result<usize, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+
+    // Counted from the breaks the buffer would be saved with now, not those it was read with.
+    let breaks = line_breaks(file);
+    let current = EndingCounts::tally(&breaks);
+    let already = match target {
+        LineEnding::Lf => current.lf,
+        LineEnding::Crlf => current.crlf,
+        LineEnding::Cr => current.cr,
+    };
+    let changed = current.total().saturating_sub(already);
+    if changed == 0 && file.line_ending == target {
+        return Ok(0);
+    }
+
+    file.history.push(LineEndingChange {
+        old: file.line_ending,
+        old_counts: file.ending_counts,
+        old_breaks: breaks,
+        new: target,
+    });
+    history::emit_availability(app, &mut file.history);
+    file.line_ending = target;
+    file.breaks = vec![target; file.lines.len() - 1];
+    file.ending_counts = EndingCounts::uniform(target, file.breaks.len());
+    mark_modified(file);
+    emit_dirty(app, file);
+    // Line contents don't change, so the one buffer-level event is all the view needs.
+    emit_line_ending(app, target);
+    Ok(changed)
+}
@@ -0,0 +1,136 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{detect_language_from_extension, is_binary_content, resolve_path};
+
+const SNIFF_BYTES: usize = 8000;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileTypeInfo {
+    category: &'static str,
+    language: Option<String>,
+    mime: String,
+}
+
+// (offset, magic bytes, category, mime)
+const MAGIC: &[(usize, &[u8], &str, &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "image", "image/png"),
+    (0, b"\xff\xd8\xff", "image", "image/jpeg"),
+    (0, b"GIF87a", "image", "image/gif"),
+    (0, b"GIF89a", "image", "image/gif"),
+    (8, b"WEBP", "image", "image/webp"),
+    (0, b"BM", "image", "image/bmp"),
+    (0, b"\x00\x00\x01\x00", "image", "image/x-icon"),
+    (0, b"PK\x03\x04", "archive", "application/zip"),
+    (0, b"\x1f\x8b", "archive", "application/gzip"),
+    (
+        0,
+        b"7z\xbc\xaf\x27\x1c",
+        "archive",
+        "application/x-7z-compressed",
+    ),
+    (0, b"Rar!\x1a\x07", "archive", "application/vnd.rar"),
+    (0, b"\xfd7zXZ\x00", "archive", "application/x-xz"),
+    (0, b"BZh", "archive", "application/x-bzip2"),
+    (257, b"ustar", "archive", "application/x-tar"),
+    (0, b"ID3", "audio", "audio/mpeg"),
+    (0, b"OggS", "audio", "audio/ogg"),
+    (0, b"fLaC", "audio", "audio/flac"),
+    (8, b"WAVE", "audio", "audio/wav"),
+    (4, b"ftyp", "video", "video/mp4"),
+    (0, b"\x1a\x45\xdf\xa3", "video", "video/webm"),
+    (0, b"%PDF-", "binary", "application/pdf"),
+    (0, b"\x7fELF", "binary", "application/x-executable"),
+    (0, b"\x00asm", "binary", "application/wasm"),
+];
+
+fn sniff_magic(bytes: &[u8]) -> Option<(&'static str, &'static str)> {
+    MAGIC
+        .iter()
+        .find(|(offset, magic, _, _)| bytes.get(*offset..offset + magic.len()) == Some(*magic))
+        .map(|(_, _, category, mime)| (*category, *mime))
+}
+
+fn mime_from_extension(ext: &str) -> Option<(&'static str, &'static str)> {
+    Some(match ext {
+        "svg" => ("image", "image/svg+xml"),
+        "png" => ("image", "image/png"),
+        "jpg" | "jpeg" => ("image", "image/jpeg"),
+        "gif" => ("image", "image/gif"),
+        "bmp" => ("image", "image/bmp"),
+        "webp" => ("image", "image/webp"),
+        "mp3" => ("audio", "audio/mpeg"),
+        "wav" => ("audio", "audio/wav"),
+        "mp4" | "m4v" => ("video", "video/mp4"),
+        "webm" => ("video", "video/webm"),
+        "zip" => ("archive", "application/zip"),
+        "tar" => ("archive", "application/x-tar"),
+        "gz" | "tgz" => ("archive", "application/gzip"),
+        "json" => ("text", "application/json"),
+        "html" | "htm" => ("text", "text/html"),
+        "css" => ("text", "text/css"),
+        "js" | "jsx" | "mjs" => ("text", "text/javascript"),
+        "md" | "markdown" => ("text", "text/markdown"),
+        "xml" => ("text", "application/xml"),
+        _ => return None,
+    })
+}
+
+// The sample may end mid-character, which still counts as valid UTF-8.
+fn looks_like_text(bytes: &[u8]) -> bool {
+    if is_binary_content(bytes) {
+        return false;
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn detect(path: &Path, bytes: &[u8]) -> FileTypeInfo {
+    let ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+
+    let by_ext = mime_from_extension(&ext);
+    // Short signatures like `BM` or `ID3` also start ordinary text, so only binary content is sniffed.
+    if looks_like_text(bytes) {
+        let (category, mime) = match by_ext {
+            Some(("image", mime)) => ("image", mime),
+            Some((_, mime)) => ("text", mime),
+            None => ("text", "text/plain"),
+        };
+        return FileTypeInfo {
+            category,
+            language: (category == "text")
+                .then(|| detect_language_from_extension(&path.to_path_buf())),
+            mime: mime.to_string(),
+        };
+    }
+
+    let (category, mime) = sniff_magic(bytes)
+        .or(by_ext.filter(|(category, _)| *category != "text"))
+        .unwrap_or(("binary", "application/octet-stream"));
+    FileTypeInfo {
+        category,
+        language: None,
+        mime: mime.to_string(),
+    }
+}
+
+#[tauri::command]
+pub fn file_type_info(app: AppHandle, path: String) -> Result<FileTypeInfo, String> {
+    let pb = resolve_path(&app, &path)?;
+    let mut sample = Vec::with_capacity(SNIFF_BYTES);
+    File::open(&pb)
+        .and_then(|f| f.take(SNIFF_BYTES as u64).read_to_end(&mut sample))
+        .map_err(|e| e.to_string())?;
+    Ok(detect(&pb, &sample))
+}
@@ -0,0 +1,99 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+use tauri::{AppHandle, State};
+
+use crate::ai::configure_hidden;
+use crate::history::{self, LineSplice};
+use crate::{anchors, edit, mark_modified, settings, split_lines, EditorState};
+
+fn formatter_for(language: &str) -> Option<String> {
+    settings::get("formatters")
+        .and_then(|v| v.get(language).cloned())
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+}
+
+fn run_formatter(command: &str, input: String) -> Result<String, String> {
+    let mut parts = command.split_whitespace();
+    let program = parts.next().ok_or("formatter command is empty")?;
+    let mut cmd = Command::new(program);
+    cmd.args(parts);
+    configure_hidden(&mut cmd);
+    cmd.stdin(Stdio::piped());
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", program, e))?;
+    let mut stdin = child.stdin.take().ok_or("failed to open formatter stdin")?;
+    // Written from another thread so a formatter that fills its stdout pipe can't deadlock us.
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let _ = writer.join();
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(if stderr.is_empty() {
+            format!("{} exited with {}", program, output.status)
+        } else {
+            stderr
+        });
+    }
+    String::from_utf8(output.stdout).map_err(|_| "formatter output is not valid UTF-8".into())
+}
+
+// The buffer isn't locked while the formatter runs; if it was edited meanwhile the
+// result is discarded rather than clobbering the newer text.
+#[tauri::command]
+pub async fn format_buffer(app: AppHandle, state: State<'_, EditorState>) -> Result<(), String> {
+    let (language, before) = {
+        let guard = state.0.lock().unwrap();
+        let file = guard.active().ok_or("no file opened")?;
+        (file.language.clone(), file.lines.clone())
+    };
+    let command = formatter_for(&language)
+        .ok_or_else(|| format!("no formatter configured for {}", language))?;
+
+    let input = before.join("\n");
+    let formatted = tokio::task::spawn_blocking(move || run_formatter(&command, input))
+        .await
+        .map_err(|e| e.to_string())??;
+    let after = split_lines(&formatted);
+
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    if file.lines != before {
+        return Err("buffer changed while formatting".into());
+    }
+    if after == before {
+        return Ok(());
+    }
+
+    let old_total = file.lines.len();
+    let mut group = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, &before, &after) {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        if tag == DiffTag::Equal {
+            continue;
+        }
+        let start = new_range.start;
+        let new: Vec<String> = after[new_range].to_vec();
+        let old: Vec<String> = file
+            .lines
+            .splice(start..start + old_range.len(), new.iter().cloned())
+            .collect();
+        anchors::shift_lines(file, start, new.len(), old.len());
+        group.push(LineSplice { start, old, new });
+    }
+
+    let first = group.first().map(|s| s.start).unwrap_or(0);
+    let end = group
+        .last()
+        .map(|s| s.start + s.new.len().max(s.old.len()))
+        .unwrap_or(first);
+    mark_modified(file);
+    edit::emit_changed(&app, file, first, end, old_total);
+    file.history.push(group);
+    history::emit_availability(&app, &mut file.history);
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::ai::configure_hidden;
+use crate::resolve_path;
+
+/// Keyed by path and the commit blamed at, `None` being the working tree.
+type BlameCache = HashMap<(PathBuf, Option<String>), (SystemTime, Vec<BlameLine>)>;
+
+const MIN_SHA_LEN: usize = 7;
+
+static BLAME_CACHE: Mutex<Option<BlameCache>> = Mutex::new(None);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameLine {
+    line: usize,
+    commit: String,
+    author: String,
+    timestamp: i64,
+}
+
+#[derive(Default)]
+struct CommitInfo {
+    author: String,
+    timestamp: i64,
+}
+
+fn run_git(dir: &Path, args: &[&str]) -> Result<Output, String> {
+    let mut cmd = Command::new("git");
+    cmd.arg("-C").arg(dir).args(args);
+    configure_hidden(&mut cmd);
+    cmd.output()
+        .map_err(|e| format!("failed to run git: {}", e))
+}
+
+fn is_sha(text: &str) -> bool {
+    text.len() >= MIN_SHA_LEN && text.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// Abbreviated hashes and refs alike are left to git to resolve, so a tag or `HEAD~2`
+// works as well as a hash prefix; ambiguity is git's error to report.
+fn resolve_commit(dir: &Path, rev: &str) -> Result<String, String> {
+    if rev.starts_with('-') {
+        return Err(format!("invalid revision: {}", rev));
+    }
+    let spec = format!("{}^{{commit}}", rev);
+    let out = run_git(dir, &["rev-parse", "--verify", "--quiet", &spec])?;
+    let sha = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    if !out.status.success() || !is_sha(&sha) {
+        return Err(format!("{} does not name a commit", rev));
+    }
+    Ok(sha)
+}
+
+// Commit details are only written out the first time a commit appears, so they are
+// remembered per hash and attached to every line that commit owns.
+fn parse_porcelain(output: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<String, CommitInfo> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut current: Option<(String, usize)> = None;
+
+    for row in output.lines() {
+        if row.starts_with('\t') {
+            if let Some((sha, final_line)) = current.take() {
+                let info = commits.entry(sha.clone()).or_default();
+                lines.push(BlameLine {
+                    line: final_line.saturating_sub(1),
+                    commit: sha.chars().take(8).collect(),
+                    author: info.author.clone(),
+                    timestamp: info.timestamp,
+                });
+            }
+            continue;
+        }
+
+        let sha = current.as_ref().map(|(s, _)| s.clone());
+        if let Some(author) = row.strip_prefix("author ") {
+            if let Some(sha) = sha {
+                commits.entry(sha).or_default().author = author.to_string();
+            }
+        } else if let Some(time) = row.strip_prefix("author-time ") {
+            if let Some(sha) = sha {
+                commits.entry(sha).or_default().timestamp = time.parse().unwrap_or(0);
+            }
+        } else {
+            let mut parts = row.split(' ');
+            if let (Some(sha), Some(_), Some(final_line)) =
+                (parts.next(), parts.next(), parts.next())
+            {
+                if is_sha(sha) {
+                    if let Ok(n) = final_line.parse() {
+                        current = Some((sha.to_string(), n));
+                    }
+                }
+            }
+        }
+    }
+
+    lines.sort_by_key(|l| l.line);
+    lines
+}
+
+fn blame_uncached(path: &Path, commit: Option<&str>) -> Result<Vec<BlameLine>, String> {
+    let dir = path.parent().ok_or("path has no parent directory")?;
+    let file = path.to_string_lossy().to_string();
+
+    if !run_git(dir, &["rev-parse", "--show-toplevel"])?
+        .status
+        .success()
+    {
+        return Err(format!("{} is not inside a git repository", file));
+    }
+    // At an older commit git's own error says whether the path existed there.
+    if commit.is_none()
+        && !run_git(dir, &["ls-files", "--error-unmatch", "--", &file])?
+            .status
+            .success()
+    {
+        return Err(format!("{} is not tracked by git", file));
+    }
+
+    let mut args = vec!["blame", "--porcelain"];
+    args.extend(commit);
+    args.extend(["--", &file]);
+    let out = run_git(dir, &args)?;
+    if !out.status.success() {
+        return Err(String::from_utf8_lossy(&out.stderr).trim().to_string());
+    }
+    Ok(parse_porcelain(&String::from_utf8_lossy(&out.stdout)))
+}
+
+#[tauri::command]
+pub async fn git_blame(
+    app: AppHandle,
+    path: String,
+    rev: Option<String>,
+) -> Result<Vec<BlameLine>, String> {
+    let path = resolve_path(&app, &path)?;
+    let mtime = fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .map_err(|e| e.to_string())?;
+
+    let (commit, path) = tokio::task::spawn_blocking(move || {
+        let dir = path.parent().ok_or("path has no parent directory")?;
+        let commit = rev.map(|r| resolve_commit(dir, &r)).transpose()?;
+        Ok::<_, String>((commit, path))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let key = (path.clone(), commit.clone());
+
+    if let Some((cached_mtime, lines)) = BLAME_CACHE
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .get(&key)
+    {
+        if *cached_mtime == mtime {
+            return Ok(lines.clone());
+        }
+    }
+
+    let lines = tokio::task::spawn_blocking(move || blame_uncached(&path, commit.as_deref()))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    BLAME_CACHE
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(key, (mtime, lines.clone()));
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abbreviated_hashes_are_accepted_in_porcelain_headers() {
+        let output =
+            "1a2b3c4d 1 1 1\nauthor Ana\nauthor-time 100\n\tfirst\n1a2b3c4d 2 2\n\tsecond\n";
+        let lines = parse_porcelain(output);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].line, 1);
+        assert_eq!(lines[1].commit, "1a2b3c4d");
+        assert_eq!(lines[1].author, "Ana");
+        assert!(!is_sha("1a2b3c"));
+        assert!(!is_sha("previous"));
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use tree_sitter::{Node, Point, Query, QueryCursor, StreamingIterator, Tree};
+
+use crate::get_ts_language;
+
+// Canonical highlight names shared by tokenization output and themes. Dotted
+// sub-scopes such as `function.method` are accepted wherever the base name is.
+pub const HIGHLIGHT_NAMES: &[&str] = &[
+    "attribute",
+    "boolean",
+    "comment",
+    "constant",
+    "constructor",
+    "escape",
+    "function",
+    "keyword",
+    "label",
+    "module",
+    "number",
+    "operator",
+    "property",
+    "punctuation",
+    "string",
+    "tag",
+    "type",
+    "variable",
+    "untokenized",
+];
+
+pub fn is_known_highlight(name: &str) -> bool {
+    let base = name.split('.').next().unwrap_or(name);
+    HIGHLIGHT_NAMES.contains(&base)
+}
+
+// Maps a raw tree-sitter leaf kind onto the closest canonical highlight name. Scopes
+// that are already canonical come back as their base name.
+pub fn highlight_for_kind(kind: &str) -> &'static str {
+    let base = kind.split('.').next().unwrap_or(kind);
+    if let Some(name) = HIGHLIGHT_NAMES.iter().find(|n| **n == base) {
+        return name;
+    }
+    match kind {
+        "untokenized" => "untokenized",
+        "identifier" => "variable",
+        "field_identifier" | "property_identifier" | "shorthand_property_identifier" => "property",
+        "type_identifier" | "primitive_type" | "predefined_type" => "type",
+        "true" | "false" => "boolean",
+        "escape_sequence" => "escape",
+        "tag_name" => "tag",
+        "attribute_name" => "attribute",
+        _ if kind.contains("comment") => "comment",
+        _ if kind.contains("string") || kind.contains("char") => "string",
+        _ if kind.contains("integer") || kind.contains("float") || kind.contains("number") => {
+            "number"
+        }
+        _ if kind.chars().all(|c| c.is_ascii_lowercase()) => "keyword",
+        _ if !kind.chars().any(|c| c.is_alphanumeric()) => "punctuation",
+        _ => "untokenized",
+    }
+}
+
+struct HighlightQuery {
+    query: Query,
+    /// Neovim-style queries list general patterns first and let later ones override;
+    /// the grammars' own tree-sitter-style queries give the first match precedence.
+    last_wins: bool,
+}
+
+type QueryCache = HashMap<String, Option<Arc<HighlightQuery>>>;
+static QUERIES: Mutex<Option<QueryCache>> = Mutex::new(None);
+
+// TypeScript's query only covers what it adds, so it is layered over JavaScript's; C++
+// does the same over C. Dart and PowerShell ship no highlight query.
+fn query_source(language: &str) -> Option<(String, bool)> {
+    let js = tree_sitter_javascript::HIGHLIGHT_QUERY;
+    let jsx = tree_sitter_javascript::JSX_HIGHLIGHT_QUERY;
+    let ts = tree_sitter_typescript::HIGHLIGHTS_QUERY;
+    let source = match language {
+        "rust" => tree_sitter_rust::HIGHLIGHTS_QUERY.to_string(),
+        "javascript" => format!("{}\n{}", jsx, js),
+        "typescript" => format!("{}\n{}", ts, js),
+        "tsx" => format!("{}\n{}\n{}", ts, jsx, js),
+        "json" => tree_sitter_json::HIGHLIGHTS_QUERY.to_string(),
+        "css" => tree_sitter_css::HIGHLIGHTS_QUERY.to_string(),
+        "html" => tree_sitter_html::HIGHLIGHTS_QUERY.to_string(),
+        "markdown" => tree_sitter_md::HIGHLIGHT_QUERY_BLOCK.to_string(),
+        "python" => tree_sitter_python::HIGHLIGHTS_QUERY.to_string(),
+        "go" => tree_sitter_go::HIGHLIGHTS_QUERY.to_string(),
+        "java" => tree_sitter_java::HIGHLIGHTS_QUERY.to_string(),
+        "c" => tree_sitter_c::HIGHLIGHT_QUERY.to_string(),
+        "cpp" => format!(
+            "{}\n{}",
+            tree_sitter_cpp::HIGHLIGHT_QUERY,
+            tree_sitter_c::HIGHLIGHT_QUERY
+        ),
+        "php" => tree_sitter_php::HIGHLIGHTS_QUERY.to_string(),
+        "ruby" => tree_sitter_ruby::HIGHLIGHTS_QUERY.to_string(),
+        "bash" => tree_sitter_bash::HIGHLIGHT_QUERY.to_string(),
+        "ocaml" => tree_sitter_ocaml::HIGHLIGHTS_QUERY.to_string(),
+        "zig" => return Some((tree_sitter_zig::HIGHLIGHTS_QUERY.to_string(), true)),
+        "lua" => return Some((tree_sitter_lua::HIGHLIGHTS_QUERY.to_string(), true)),
+        "haskell" => return Some((tree_sitter_haskell::HIGHLIGHTS_QUERY.to_string(), true)),
+        "swift" => return Some((tree_sitter_swift::HIGHLIGHTS_QUERY.to_string(), true)),
+        _ => return None,
+    };
+    Some((source, false))
+}
+
+fn highlight_query(language: &str) -> Option<Arc<HighlightQuery>> {
+    let language = language.to_ascii_lowercase();
+    let mut guard = QUERIES.lock().unwrap();
+    let cache = guard.get_or_insert_with(HashMap::new);
+    if let Some(cached) = cache.get(&language) {
+        return cached.clone();
+    }
+    let compiled = query_source(&language).and_then(|(source, last_wins)| {
+        let ts_language = get_ts_language(&language)?;
+        match Query::new(&ts_language, &source) {
+            Ok(query) => Some(Arc::new(HighlightQuery { query, last_wins })),
+            Err(e) => {
+                eprintln!("Failed to compile {} highlight query: {}", language, e);
+                None
+            }
+        }
+    });
+    cache.insert(language, compiled.clone());
+    compiled
+}
+
+// Capture names outside HIGHLIGHT_NAMES are folded onto the closest canonical one; captures
+// that only exist for editor features (spell checking, injections) produce no token.
+fn scope_for_capture(name: &str) -> Option<&str> {
+    if name.starts_with('_') || matches!(name, "none" | "spell" | "embedded") {
+        return None;
+    }
+    if is_known_highlight(name) {
+        return Some(name);
+    }
+    let base = name.split('.').next().unwrap_or(name);
+    Some(match base {
+        "delimiter" => "punctuation.delimiter",
+        "character" => "string",
+        "field" => "property",
+        "parameter" => "variable.parameter",
+        "method" => "function.method",
+        "repeat" | "conditional" | "preproc" | "include" | "exception" => "keyword",
+        "namespace" => "module",
+        "float" => "number",
+        _ => match name {
+            "text.title" => "keyword",
+            "text.literal" => "string",
+            "text.uri" | "text.reference" => "string.special",
+            _ => return None,
+        },
+    })
+}
+
+fn node_text<'a>(lines: &'a [String], node: Node) -> std::vec::IntoIter<&'a [u8]> {
+    let (start, end) = (node.start_position(), node.end_position());
+    let mut pieces = Vec::new();
+    for row in start.row..=end.row {
+        let line = match lines.get(row) {
+            Some(l) => l.as_bytes(),
+            None => break,
+        };
+        let from = if row == start.row {
+            start.column.min(line.len())
+        } else {
+            0
+        };
+        let to = if row == end.row {
+            end.column.min(line.len())
+        } else {
+            line.len()
+        };
+        if row > start.row {
+            pieces.push(&b"\n"[..]);
+        }
+        pieces.push(&line[from..to.max(from)]);
+    }
+    pieces.into_iter()
+}
+
+/// Highlight captures on rows `start..=end`, or `None` when the language has no query.
+/// A node captured by several patterns keeps only the capture that takes precedence.
+pub fn query_tokens(
+    language: &str,
+    tree: &Tree,
+    lines: &[String],
+    start: usize,
+    end: usize,
+) -> Option<Vec<(Point, Point, String)>> {
+    let hq = highlight_query(language)?;
+    let names = hq.query.capture_names();
+    let mut cursor = QueryCursor::new();
+    cursor.set_point_range(
+        Point {
+            row: start,
+            column: 0,
+        }..Point {
+            row: end + 1,
+            column: 0,
+        },
+    );
+
+    let mut chosen: HashMap<(usize, usize), (usize, Point, Point, &str)> = HashMap::new();
+    let mut captures = cursor.captures(&hq.query, tree.root_node(), |n: Node| node_text(lines, n));
+    while let Some((m, idx)) = captures.next() {
+        // Predicates other than the built-in text ones (`#lua-match?` and friends) can't be
+        // evaluated here, and ignoring them would let the pattern match everything.
+        if !hq.query.general_predicates(m.pattern_index).is_empty() {
+            continue;
+        }
+        let capture = m.captures[*idx];
+        let scope = match scope_for_capture(names[capture.index as usize]) {
+            Some(s) => s,
+            None => continue,
+        };
+        let node = capture.node;
+        let key = (node.start_byte(), node.end_byte());
+        let wins = match chosen.get(&key) {
+            None => true,
+            Some((pattern, ..)) if hq.last_wins => m.pattern_index >= *pattern,
+            Some((pattern, ..)) => m.pattern_index < *pattern,
+        };
+        if wins {
+            chosen.insert(
+                key,
+                (
+                    m.pattern_index,
+                    node.start_position(),
+                    node.end_position(),
+                    scope,
+                ),
+            );
+        }
+    }
+
+    let mut tokens: Vec<(Point, Point, String)> = chosen
+        .into_values()
+        .map(|(_, sp, ep, scope)| (sp, ep, scope.to_string()))
+        .collect();
+    tokens.sort_by_key(|(sp, ep, _)| (sp.row, sp.column, std::cmp::Reverse((ep.row, ep.column))));
+    Some(tokens)
+}
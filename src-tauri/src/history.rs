@@ -0,0 +1,305 @@
+use std::collections::VecDeque;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::endings::{self, EndingCounts, LineEndingChange};
+use crate::{anchors, emit_dirty, mark_modified, settings, EditorState};
+
+pub const DEFAULT_MAX_UNDO_ENTRIES: usize = 500;
+const COALESCE_WINDOW: Duration = Duration::from_millis(1000);
+
+// Replacing `old` with `new` starting at line `start`; every buffer edit reduces to one of these.
+#[derive(Clone)]
+pub struct LineSplice {
+    pub start: usize,
+    pub old: Vec<String>,
+    pub new: Vec<String>,
+}
+
+impl LineSplice {
+    fn approx_bytes(&self) -> usize {
+        let text: usize = self
+            .old
+            .iter()
+            .chain(self.new.iter())
+            .map(|l| l.len())
+            .sum();
+        text + (self.old.len() + self.new.len()) * size_of::<String>() + size_of::<Self>()
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct UndoGroup {
+    pub splices: Vec<LineSplice>,
+    pub line_ending: Option<LineEndingChange>,
+}
+
+impl UndoGroup {
+    fn is_empty(&self) -> bool {
+        self.splices.is_empty() && self.line_ending.is_none()
+    }
+}
+
+impl From<Vec<LineSplice>> for UndoGroup {
+    fn from(splices: Vec<LineSplice>) -> Self {
+        UndoGroup {
+            splices,
+            line_ending: None,
+        }
+    }
+}
+
+impl From<LineEndingChange> for UndoGroup {
+    fn from(change: LineEndingChange) -> Self {
+        UndoGroup {
+            splices: Vec::new(),
+            line_ending: Some(change),
+        }
+    }
+}
+
+fn group_bytes(group: &UndoGroup) -> usize {
+    group
+        .splices
+        .iter()
+        .map(|s| s.approx_bytes())
+        .sum::<usize>()
+        + size_of::<UndoGroup>()
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryInfo {
+    entries: usize,
+    approx_bytes: usize,
+    can_undo: bool,
+    can_redo: bool,
+}
+
+#[derive(Default)]
+pub struct History {
+    undo: VecDeque<UndoGroup>,
+    redo: Vec<UndoGroup>,
+    bytes: usize,
+    // When the newest undo group may still absorb follow-up keystrokes.
+    coalesce_until: Option<Instant>,
+    // (can undo, can redo) as last sent in `history-changed`.
+    reported: (bool, bool),
+}
+
+impl History {
+    pub fn push(&mut self, group: impl Into<UndoGroup>) {
+        let group = group.into();
+        if group.is_empty() {
+            return;
+        }
+        for g in self.redo.drain(..) {
+            self.bytes -= group_bytes(&g);
+        }
+        self.bytes += group_bytes(&group);
+        self.undo.push_back(group);
+        self.coalesce_until = None;
+        self.evict();
+    }
+
+    // Merges a single-line edit into the previous one when it continues it on the
+    // same row shortly after, so undo reverts a burst of typing at once.
+    pub fn push_coalescing(&mut self, splice: LineSplice) {
+        let now = Instant::now();
+        let single_line = splice.old.len() == 1 && splice.new.len() == 1;
+        let can_merge = single_line
+            && self.redo.is_empty()
+            && self.coalesce_until.is_some_and(|until| now <= until);
+
+        if can_merge {
+            if let Some(last) = self.undo.back_mut().and_then(|g| g.splices.last_mut()) {
+                if last.start == splice.start && last.new.len() == 1 && last.new == splice.old {
+                    let before = last.approx_bytes();
+                    last.new = splice.new;
+                    self.bytes = self.bytes - before + last.approx_bytes();
+                    self.coalesce_until = Some(now + COALESCE_WINDOW);
+                    return;
+                }
+            }
+        }
+
+        self.push(vec![splice]);
+        if single_line {
+            self.coalesce_until = Some(now + COALESCE_WINDOW);
+        }
+    }
+
+    fn evict(&mut self) {
+        let max_entries = settings::get("max_undo_entries")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize)
+            .unwrap_or(DEFAULT_MAX_UNDO_ENTRIES);
+        let max_bytes = settings::get("max_undo_bytes")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as usize);
+
+        while self.undo.len() > max_entries || max_bytes.is_some_and(|max| self.bytes > max) {
+            match self.undo.pop_front() {
+                Some(g) => self.bytes -= group_bytes(&g),
+                None => break,
+            }
+        }
+    }
+
+    pub fn pop_undo(&mut self) -> Option<UndoGroup> {
+        self.coalesce_until = None;
+        let group = self.undo.pop_back()?;
+        self.redo.push(group.clone());
+        Some(group)
+    }
+
+    pub fn pop_redo(&mut self) -> Option<UndoGroup> {
+        let group = self.redo.pop()?;
+        self.undo.push_back(group.clone());
+        Some(group)
+    }
+
+    pub fn clear(&mut self) {
+        self.coalesce_until = None;
+        self.undo.clear();
+        self.redo.clear();
+        self.bytes = 0;
+    }
+
+    pub fn info(&self) -> HistoryInfo {
+        HistoryInfo {
+            entries: self.undo.len() + self.redo.len(),
+            approx_bytes: self.bytes,
+            can_undo: !self.undo.is_empty(),
+            can_redo: !self.redo.is_empty(),
+        }
+    }
+}
+
+/// Emits `history-changed` when undo or redo availability differs from what the UI was
+/// last told. Call after anything that pushes, pops or clears history.
+pub fn emit_availability(app: &AppHandle, history: &mut History) {
+    if (!history.undo.is_empty(), !history.redo.is_empty()) != history.reported {
+        announce_availability(app, history);
+    }
+}
+
+/// Emits `history-changed` unconditionally, for a buffer the UI has just switched to.
+pub fn announce_availability(app: &AppHandle, history: &mut History) {
+    let now = (!history.undo.is_empty(), !history.redo.is_empty());
+    history.reported = now;
+    app.emit(
+        "history-changed",
+        serde_json::json!({ "canUndo": now.0, "canRedo": now.1 }),
+    )
+    .map_err(|e| e.to_string())
+    .ok();
+}
+
+fn splice_lines(lines: &mut Vec<String>, start: usize, remove: usize, insert: &[String]) {
+    let start = start.min(lines.len());
+    let end = (start + remove).min(lines.len());
+    lines.splice(start..end, insert.iter().cloned());
+}
+
+fn apply_history(app: &AppHandle, state: &EditorState, undo: bool) -> Result<HistoryInfo, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    let group = if undo {
+        file.history.pop_undo()
+    } else {
+        file.history.pop_redo()
+    };
+    let group = match group {
+        Some(g) => g,
+        None => return Ok(file.history.info()),
+    };
+
+    let mut first_line = usize::MAX;
+    if let Some(change) = group.line_ending {
+        if undo {
+            file.line_ending = change.old;
+            file.ending_counts = change.old_counts;
+            file.breaks = change.old_breaks;
+        } else {
+            let breaks = file.lines.len().saturating_sub(1);
+            file.line_ending = change.new;
+            file.ending_counts = EndingCounts::uniform(change.new, breaks);
+            file.breaks = vec![change.new; breaks];
+        }
+        endings::emit_line_ending(app, file.line_ending);
+        first_line = 0;
+    }
+    if undo {
+        for s in group.splices.iter().rev() {
+            splice_lines(&mut file.lines, s.start, s.new.len(), &s.old);
+            anchors::shift_lines(file, s.start, s.old.len(), s.new.len());
+            first_line = first_line.min(s.start);
+        }
+    } else {
+        for s in group.splices.iter() {
+            splice_lines(&mut file.lines, s.start, s.old.len(), &s.new);
+            anchors::shift_lines(file, s.start, s.new.len(), s.old.len());
+            first_line = first_line.min(s.start);
+        }
+    }
+    mark_modified(file);
+    emit_dirty(app, file);
+    anchors::emit_updates(app, file);
+    emit_availability(app, &mut file.history);
+
+    app.emit(
+        "file-structure-changed",
+        serde_json::json!({
+          "kind": if undo { "undo" } else { "redo" },
+          "start": first_line,
+          "totalLines": file.lines.len()
+        }),
+    )
+    .map_err(|e| e.to_string())
+    .ok();
+
+    for i in first_line.min(file.lines.len())..file.lines.len() {
+        app.emit(
+            "file-updated",
+            serde_json::json!({
+              "line": i,
+              "content": file.lines[i],
+              "totalLines": file.lines.len()
+            }),
+        )
+        .map_err(|e| e.to_string())
+        .ok();
+    }
+
+    Ok(file.history.info())
+}
+
+#[tauri::command]
+pub fn undo(app: AppHandle, state: State<'_, EditorState>) -> Result<HistoryInfo, String> {
+    apply_history(&app, &state, true)
+}
+
+#[tauri::command]
+pub fn redo(app: AppHandle, state: State<'_, EditorState>) -> Result<HistoryInfo, String> {
+    apply_history(&app, &state, false)
+}
+
+#[tauri::command]
+pub fn undo_history_info(state: State<'_, EditorState>) -> Result<HistoryInfo, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    Ok(file.history.info())
+}
+
+#[tauri::command]
+pub fn clear_undo_history(app: AppHandle, state: State<'_, EditorState>) -> Result<(), String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    file.history.clear();
+    emit_availability(&app, &mut file.history);
+    Ok(())
+}
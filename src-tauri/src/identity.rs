@@ -0,0 +1,79 @@
+use std::fs;
+use std::time::SystemTime;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::State;
+
+use crate::{endings, EditorState, FileState};
+
+/// Hashes remembered alongside the buffer so repeated identity requests stay cheap.
+#[derive(Default)]
+pub struct HashCache {
+    /// (buffer version, hash)
+    buffer: Option<(u64, String)>,
+    /// (mtime, length, hash) of the file on disk.
+    disk: Option<(SystemTime, u64, String)>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BufferIdentity {
+    path: String,
+    buffer_hash: String,
+    /// `None` when the file no longer exists on disk.
+    disk_hash: Option<String>,
+    dirty: bool,
+    version: u64,
+}
+
+// SHA-256 rather than std's hasher, whose output may change between Rust releases and
+// would silently invalidate every cache keyed on it.
+pub fn content_hash(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Hashed as it would be saved, so a clean buffer's hash matches the disk hash.
+fn buffer_hash(file: &mut FileState) -> String {
+    if let Some((version, hash)) = &file.identity.buffer {
+        if *version == file.version {
+            return hash.clone();
+        }
+    }
+    let hash = content_hash(endings::saved_text(file).0.as_bytes());
+    file.identity.buffer = Some((file.version, hash.clone()));
+    hash
+}
+
+fn disk_hash(file: &mut FileState) -> Option<String> {
+    let meta = fs::metadata(&file.path).ok()?;
+    let mtime = meta.modified().ok()?;
+    if let Some((cached_mtime, len, hash)) = &file.identity.disk {
+        if *cached_mtime == mtime && *len == meta.len() {
+            return Some(hash.clone());
+        }
+    }
+    let hash = content_hash(&fs::read(&file.path).ok()?);
+    file.identity.disk = Some((mtime, meta.len(), hash.clone()));
+    Some(hash)
+}
+
+#[tauri::command]
+pub fn buffer_identity(state: State<'_, EditorState>) -> Result<BufferIdentity, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    let path = file
+        .path
+        .canonicalize()
+        .unwrap_or_else(|_| file.path.clone());
+    Ok(BufferIdentity {
+        path: path.to_string_lossy().to_string(),
+        buffer_hash: buffer_hash(file),
+        disk_hash: disk_hash(file),
+        dirty: file.dirty,
+        version: file.version,
+    })
+}
@@ -0,0 +1,376 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{edit, history, settings, EditorState, FileState, Offset};
+
+const DEFAULT_TAB_WIDTH: usize = 4;
+
+/// Per-buffer overrides set for this session; `None` falls back to settings.
+#[derive(Clone, Default)]
+pub struct IndentOverride {
+    tab_width: Option<usize>,
+    use_spaces: Option<bool>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndentConfig {
+    pub tab_width: usize,
+    pub use_spaces: bool,
+}
+
+pub fn effective_indent(file: &FileState) -> IndentConfig {
+    IndentConfig {
+        tab_width: file.indent.tab_width.unwrap_or_else(|| {
+            settings::get("tab_width")
+                .and_then(|v| v.as_u64())
+                .map(|v| v as usize)
+                .unwrap_or(DEFAULT_TAB_WIDTH)
+        }),
+        use_spaces: file
+            .indent
+            .use_spaces
+            .unwrap_or_else(|| settings::get_bool("use_spaces", true)),
+    }
+}
+
+pub fn indent_width(line: &str, tab_width: usize) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(|c| if c == '\t' { tab_width } else { 1 })
+        .sum()
+}
+
+fn render_indent(width: usize, config: &IndentConfig) -> String {
+    if config.use_spaces {
+        " ".repeat(width)
+    } else {
+        let mut s = "\t".repeat(width / config.tab_width);
+        s.push_str(&" ".repeat(width % config.tab_width));
+        s
+    }
+}
+
+fn emit_config(app: &AppHandle, file: &FileState) -> IndentConfig {
+    let config = effective_indent(file);
+    app.emit("indent-config-changed", &config)
+        .map_err(|e| e.to_string())
+        .ok();
+    config
+}
+
+#[tauri::command]
+pub fn set_tab_width(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    width: usize,
+) -> Result<IndentConfig, String> {
+    if width == 0 {
+        return Err("tab width must be at least 1".into());
+    }
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    file.indent.tab_width = Some(width);
+    Ok(emit_config(&app, file))
+}
+
+#[tauri::command]
+pub fn set_indent_style(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    use_spaces: bool,
+) -> Result<IndentConfig, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    file.indent.use_spaces = Some(use_spaces);
+    Ok(emit_config(&app, file))
+}
+
+#[tauri::command]
+pub fn get_indent_config(state: State<'_, EditorState>) -> Result<IndentConfig, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    Ok(effective_indent(file))
+}
+
+fn rebased(line: &str, width: usize, config: &IndentConfig) -> String {
+    render_indent(width, config) + line.trim_start_matches([' ', '\t'])
+}
+
+fn shift_line(line: &str, outdent: bool, config: &IndentConfig) -> String {
+    if line.trim().is_empty() {
+        return line.to_string();
+    }
+    let width = indent_width(line, config.tab_width);
+    let width = if outdent {
+        width.saturating_sub(config.tab_width)
+    } else {
+        width + config.tab_width
+    };
+    rebased(line, width, config)
+}
+
+fn retab_line(line: &str, config: &IndentConfig) -> String {
+    if line.trim().is_empty() {
+        return line.to_string();
+    }
+    rebased(line, indent_width(line, config.tab_width), config)
+}
+
+fn line_comment(language: &str) -> Option<&'static str> {
+    Some(match language.to_ascii_lowercase().as_str() {
+        "rust" | "javascript" | "typescript" | "tsx" | "go" | "java" | "c" | "cpp" | "php"
+        | "swift" => "//",
+        "python" | "ruby" | "bash" | "toml" | "yaml" => "#",
+        "lua" => "--",
+        _ => return None,
+    })
+}
+
+// Comments go in at the block's shallowest indent, so nested lines keep their relative
+// indentation; removing them takes back the single space that follows the marker too.
+fn toggle_lines(lines: &[String], marker: &str, config: &IndentConfig) -> Vec<String> {
+    let body = |l: &String| l.trim_start_matches([' ', '\t']).to_string();
+    let filled = || lines.iter().filter(|l| !l.trim().is_empty());
+    if filled().all(|l| body(l).starts_with(marker)) {
+        return lines
+            .iter()
+            .map(|l| match body(l).strip_prefix(marker) {
+                Some(rest) => {
+                    let rest = rest.strip_prefix(' ').unwrap_or(rest);
+                    let width =
+                        indent_width(l, config.tab_width) + indent_width(rest, config.tab_width);
+                    rebased(rest, width, config)
+                }
+                None => l.clone(),
+            })
+            .collect();
+    }
+    let base = filled()
+        .map(|l| indent_width(l, config.tab_width))
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| {
+            if l.trim().is_empty() {
+                return l.clone();
+            }
+            let inner = indent_width(l, config.tab_width) - base;
+            format!(
+                "{}{} {}",
+                render_indent(base, config),
+                marker,
+                rebased(l, inner, config)
+            )
+        })
+        .collect()
+}
+
+// Replaces rows `start..=end` as one undo step; rows that come out unchanged are no edit.
+fn rewrite_rows(
+    app: &AppHandle,
+    file: &mut FileState,
+    start: usize,
+    end: usize,
+    rows: Vec<String>,
+) -> Result<usize, String> {
+    let changed = rows
+        .iter()
+        .zip(&file.lines[start..=end])
+        .filter(|(new, old)| new != old)
+        .count();
+    if changed == 0 {
+        return Ok(0);
+    }
+    let from = Offset { row: start, col: 0 };
+    let to = Offset {
+        row: end,
+        col: file.lines[end].len(),
+    };
+    let old_total = file.lines.len();
+    let (splice, _) = edit::replace_range(file, &from, &to, &rows.join("\n"))?;
+    edit::emit_changed(app, file, start, end + 1, old_total);
+    file.history.push(vec![splice]);
+    history::emit_availability(app, &mut file.history);
+    Ok(changed)
+}
+
+fn row_range(file: &FileState, start_row: usize, end_row: usize) -> Result<(usize, usize), String> {
+    let last = file.lines.len() - 1;
+    let (start, end) = (start_row.min(last), end_row.min(last));
+    if start > end {
+        return Err("range end is before its start".into());
+    }
+    Ok((start, end))
+}
+
+/// The indentation for a line typed after `row`: that row's own, one level deeper when it
+/// ends by opening a block.
+#[tauri::command]
+pub fn suggest_indent(state: State<'_, EditorState>, row: usize) -> Result<String, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let config = effective_indent(file);
+    let line = file
+        .lines
+        .get(row)
+        .ok_or_else(|| format!("row {} is out of range", row))?;
+    let mut width = indent_width(line, config.tab_width);
+    if line.trim_end().ends_with(['{', '(', '[', ':']) {
+        width += config.tab_width;
+    }
+    Ok(render_indent(width, &config))
+}
+
+#[tauri::command]
+pub fn indent_range(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    start_row: usize,
+    end_row: usize,
+    outdent: Option<bool>,
+) -> Result<usize, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    let config = effective_indent(file);
+    let (start, end) = row_range(file, start_row, end_row)?;
+    let outdent = outdent.unwrap_or(false);
+    let rows = file.lines[start..=end]
+        .iter()
+        .map(|l| shift_line(l, outdent, &config))
+        .collect();
+    rewrite_rows(&app, file, start, end, rows)
+}
+
+/// Rewrites every line's leading whitespace in the buffer's effective indent style.
+#[tauri::command]
+pub fn retab(app: AppHandle, state: State<'_, EditorState>) -> Result<usize, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    let config = effective_indent(file);
+    let end = file.lines.len() - 1;
+    let rows = file.lines.iter().map(|l| retab_line(l, &config)).collect();
+    rewrite_rows(&app, file, 0, end, rows)
+}
+
+#[tauri::command]
+pub fn toggle_comment(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    start_row: usize,
+    end_row: usize,
+) -> Result<usize, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    let marker = line_comment(&file.language)
+        .ok_or_else(|| format!("{} has no line comment syntax", file.language))?;
+    let config = effective_indent(file);
+    let (start, end) = row_range(file, start_row, end_row)?;
+    let rows = toggle_lines(&file.lines[start..=end], marker, &config);
+    rewrite_rows(&app, file, start, end, rows)
+}
+
+// The pasted block keeps its own relative indentation but is rebased onto the
+// destination line's indent. A first line with no indent of its own is usually the
+// tail of a copied line, so it doesn't count towards the block's base indent.
+fn reindent_block(text: &str, dest_width: usize, at_indent: bool, config: &IndentConfig) -> String {
+    let lines: Vec<&str> = text.split('\n').map(|l| l.trim_end_matches('\r')).collect();
+    let measured = |i: usize, line: &&str| {
+        !line.trim().is_empty() && !(i == 0 && (!at_indent || indent_width(line, 1) == 0))
+    };
+    let base = lines
+        .iter()
+        .enumerate()
+        .filter(|(i, l)| measured(*i, l))
+        .map(|(_, l)| indent_width(l, config.tab_width))
+        .min()
+        .unwrap_or(0);
+
+    let mut out = Vec::with_capacity(lines.len());
+    for (i, line) in lines.iter().enumerate() {
+        let body = line.trim_start_matches([' ', '\t']);
+        if i == 0 {
+            out.push(if at_indent {
+                body.to_string()
+            } else {
+                line.to_string()
+            });
+        } else if body.is_empty() {
+            out.push(String::new());
+        } else {
+            let width = dest_width + indent_width(line, config.tab_width).saturating_sub(base);
+            out.push(render_indent(width, config) + body);
+        }
+    }
+    out.join("\n")
+}
+
+#[tauri::command]
+pub fn reindent_pasted(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    offset: Offset,
+    text: String,
+) -> Result<Offset, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    let config = effective_indent(file);
+    let before = file
+        .lines
+        .get(offset.row)
+        .and_then(|l| l.get(..offset.col))
+        .ok_or_else(|| format!("offset {}:{} is out of range", offset.row, offset.col))?;
+    let at_indent = before.trim().is_empty();
+    let dest_width = indent_width(before, config.tab_width);
+
+    let text = reindent_block(&text, dest_width, at_indent, &config);
+    let old_total = file.lines.len();
+    let (splice, cursor) = edit::replace_range(file, &offset, &offset, &text)?;
+    edit::emit_changed(
+        &app,
+        file,
+        splice.start,
+        splice.start + splice.new.len(),
+        old_total,
+    );
+    file.history.push(vec![splice]);
+    history::emit_availability(&app, &mut file.history);
+    Ok(cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.split('\n').map(String::from).collect()
+    }
+
+    #[test]
+    fn overrides_decide_how_lines_are_shifted_and_retabbed() {
+        let tabs = IndentConfig {
+            tab_width: 2,
+            use_spaces: false,
+        };
+        assert_eq!(shift_line("  x", false, &tabs), "\t\tx");
+        assert_eq!(shift_line("\tx", true, &tabs), "x");
+        assert_eq!(retab_line("     x", &tabs), "\t\t x");
+        assert_eq!(retab_line("   ", &tabs), "   ");
+    }
+
+    #[test]
+    fn toggling_a_comment_twice_restores_the_block() {
+        let spaces = IndentConfig {
+            tab_width: 4,
+            use_spaces: true,
+        };
+        let block = lines("    if x {\n\n        y();\n    }");
+        let commented = toggle_lines(&block, "//", &spaces);
+        assert_eq!(
+            commented,
+            lines("    // if x {\n\n    //     y();\n    // }")
+        );
+        assert_eq!(toggle_lines(&commented, "//", &spaces), block);
+    }
+}
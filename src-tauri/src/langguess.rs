@@ -0,0 +1,196 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use tauri::AppHandle;
+
+use crate::{detect_language_from_extension, resolve_path, settings};
+
+const SAMPLE_BYTES: u64 = 64 * 1024;
+/// A candidate needs at least this many distinct signals, and strictly more than any
+/// other candidate, before it replaces the extension-based language.
+const MIN_SIGNALS: usize = 2;
+
+struct Candidate {
+    language: &'static str,
+    signals: &'static [&'static str],
+}
+
+struct Ambiguity {
+    ext: &'static str,
+    candidates: &'static [Candidate],
+}
+
+const AMBIGUOUS: &[Ambiguity] = &[
+    Ambiguity {
+        ext: "h",
+        candidates: &[
+            Candidate {
+                language: "cpp",
+                signals: &[
+                    "namespace ",
+                    "template<",
+                    "template <",
+                    "std::",
+                    "public:",
+                    "private:",
+                    "protected:",
+                    "virtual ",
+                    "constexpr ",
+                    "#include <iostream>",
+                    "#include <string>",
+                    "#include <vector>",
+                ],
+            },
+            Candidate {
+                language: "objective-c",
+                signals: &["@interface", "@end", "@property", "#import ", "@protocol"],
+            },
+        ],
+    },
+    Ambiguity {
+        ext: "m",
+        candidates: &[
+            Candidate {
+                language: "objective-c",
+                signals: &[
+                    "@interface",
+                    "@implementation",
+                    "@end",
+                    "#import ",
+                    "[self ",
+                ],
+            },
+            Candidate {
+                language: "matlab",
+                signals: &[
+                    "function ",
+                    "endfunction",
+                    "disp(",
+                    "fprintf(",
+                    "zeros(",
+                    "%%",
+                ],
+            },
+        ],
+    },
+    Ambiguity {
+        ext: "pl",
+        candidates: &[
+            Candidate {
+                language: "perl",
+                signals: &[
+                    "use strict",
+                    "use warnings",
+                    "my $",
+                    "sub ",
+                    "#!/usr/bin/perl",
+                ],
+            },
+            Candidate {
+                language: "prolog",
+                signals: &[":- ", ":-\n", "?- ", "consult(", ":- module("],
+            },
+        ],
+    },
+];
+
+// `ambiguous_extensions` limits which table entries are consulted; unset means all of them.
+fn enabled(ext: &str) -> bool {
+    match settings::get("ambiguous_extensions").and_then(|v| v.as_array().cloned()) {
+        Some(list) => list.iter().any(|e| e.as_str() == Some(ext)),
+        None => true,
+    }
+}
+
+fn score(source: &str, candidate: &Candidate) -> usize {
+    candidate
+        .signals
+        .iter()
+        .filter(|s| source.contains(*s))
+        .count()
+}
+
+/// Picks among the candidates for an ambiguous extension, or `None` when the extension is
+/// not ambiguous (or disabled) or no candidate wins, and the extension mapping should be
+/// used as-is. That keeps an undecided `.h` as "h", exactly as opening it normally would.
+pub fn disambiguate(path: &Path, source: &str) -> Option<String> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let entry = AMBIGUOUS.iter().find(|a| a.ext == ext)?;
+    if !enabled(&ext) {
+        return None;
+    }
+
+    let mut scores: Vec<(usize, &str)> = entry
+        .candidates
+        .iter()
+        .map(|c| (score(source, c), c.language))
+        .collect();
+    scores.sort_by_key(|s| std::cmp::Reverse(s.0));
+    let chosen = match scores.as_slice() {
+        [(best, language), rest @ ..]
+            if *best >= MIN_SIGNALS && rest.iter().all(|(s, _)| s < best) =>
+        {
+            language
+        }
+        _ => return None,
+    };
+    Some(chosen.to_string())
+}
+
+const INTERPRETERS: &[(&str, &str)] = &[
+    ("python", "python"),
+    ("bash", "bash"),
+    ("sh", "bash"),
+    ("zsh", "bash"),
+    ("dash", "bash"),
+    ("ksh", "bash"),
+    ("node", "javascript"),
+    ("nodejs", "javascript"),
+    ("ruby", "ruby"),
+    ("lua", "lua"),
+    ("php", "php"),
+    ("pwsh", "powershell"),
+];
+
+// Handles both `#!/usr/bin/python3` and `#!/usr/bin/env -S python3 -u`; version
+// suffixes like `python3.12` are dropped before the lookup.
+fn shebang_language(source: &str) -> Option<&'static str> {
+    let line = source.lines().next()?.strip_prefix("#!")?;
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|w| !w.starts_with('-') && !w.contains('='))?;
+    }
+    let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    INTERPRETERS
+        .iter()
+        .find(|(interpreter, _)| *interpreter == name)
+        .map(|(_, language)| *language)
+}
+
+/// The extension-based language, falling back to the shebang when the path has no
+/// recognisable extension.
+pub fn detect_with_shebang(path: &Path, source: &str) -> String {
+    let language = detect_language_from_extension(&path.to_path_buf());
+    if !language.is_empty() {
+        return language;
+    }
+    shebang_language(source)
+        .map(|l| l.to_string())
+        .unwrap_or(language)
+}
+
+pub fn language_for(path: &Path, source: &str) -> String {
+    disambiguate(path, source).unwrap_or_else(|| detect_with_shebang(path, source))
+}
+
+#[tauri::command]
+pub fn guess_language_from_content(app: AppHandle, path: String) -> Result<String, String> {
+    let pb = resolve_path(&app, &path)?;
+    let mut sample = Vec::new();
+    File::open(&pb)
+        .and_then(|f| f.take(SAMPLE_BYTES).read_to_end(&mut sample))
+        .map_err(|e| e.to_string())?;
+    Ok(language_for(&pb, &String::from_utf8_lossy(&sample)))
+}
@@ -1,23 +1,62 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, VecDeque};
 use std::fs;
+use std::io::Write;
 use std::path::{Component, Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Mutex;
+use std::time::Duration;
 
 mod ai;
+mod anchors;
+mod backups;
+mod bench;
+mod bookmarks;
+mod buffers;
+mod diff;
+mod dirhash;
+mod disk;
+mod diskwatch;
+mod edit;
+mod endings;
+mod filetype;
+mod format;
+mod git;
+mod highlight;
+mod history;
+mod identity;
+mod indent;
+mod langguess;
+mod locate;
+mod partial;
 mod pools;
+mod project;
+mod related;
+mod replace;
+mod search;
+mod settings;
+mod snippet;
+mod symbols;
+mod syntax;
+mod tags;
+mod tail;
 mod task;
+mod themes;
+mod todos;
 
 use ignore::gitignore::{Gitignore, GitignoreBuilder};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use tauri::{AppHandle, Emitter, State};
-use tree_sitter::{Language, Parser, Point};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tree_sitter::{InputEdit, Language, Parser, Point, Range, Tree};
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::pools::get_file_queue_pool;
 
 static READY_ALREADY_CALLED: AtomicBool = AtomicBool::new(false);
+const DEFAULT_TASK_QUEUE_IDLE_MS: u64 = 1000;
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Offset {
     col: usize,
@@ -39,25 +78,96 @@ struct Token {
 struct FileMetadata {
     name: String,
     path: String,
+    /// Size of the in-memory buffer (line bytes plus `\n` separators).
     size: usize,
+    /// Size of the file on disk as of the last open or save.
+    disk_size: u64,
     language: String,
     line_count: usize,
+    /// Invalid UTF-8 was replaced with U+FFFD when the file was opened.
+    lossy: bool,
+    /// The file is gitignored within the current workspace root.
+    ignored: bool,
+    line_ending: endings::LineEnding,
+    /// Lines on disk use more than one ending style.
+    mixed_line_endings: bool,
+    /// The file ends with a line break. The buffer keeps it as a trailing empty line,
+    /// which is how save writes it back.
+    trailing_newline: bool,
 }
 
 struct FileState {
     path: PathBuf,
     name: String,
+    /// Size of the in-memory buffer (line bytes plus `\n` separators).
     size: usize,
+    /// Size of the file on disk as of the last open or save.
+    disk_size: u64,
     language: String,
     lines: Vec<String>,
+    /// The break after each line but the last, so mixed endings survive edits and saves.
+    breaks: Vec<endings::LineEnding>,
+    dirty: bool,
+    /// `dirty` as last sent in `dirty-changed`.
+    dirty_reported: bool,
+    history: history::History,
+    anchors: anchors::Anchors,
+    indent: indent::IndentOverride,
+    /// Ending written between lines on save.
+    line_ending: endings::LineEnding,
+    ending_counts: endings::EndingCounts,
+    /// Saving is refused until the user confirms, since it would persist U+FFFD replacements.
+    lossy: bool,
+    /// Bumped on every buffer mutation.
+    version: u64,
+    identity: identity::HashCache,
+    parse_cache: RefCell<Option<ParseCache>>,
+    disk: diskwatch::DiskWatch,
+}
+
+impl FileState {
+    fn from_text(path: PathBuf, contents: &str, language: String, lossy: bool) -> Self {
+        let ending_counts = endings::EndingCounts::of(contents);
+        FileState {
+            name: path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string(),
+            path,
+            size: contents.len(),
+            disk_size: contents.len() as u64,
+            language,
+            lines: split_lines(contents),
+            breaks: endings::breaks_of(contents),
+            dirty: false,
+            dirty_reported: false,
+            history: Default::default(),
+            anchors: Default::default(),
+            indent: Default::default(),
+            line_ending: ending_counts.dominant(),
+            ending_counts,
+            lossy,
+            version: 0,
+            identity: Default::default(),
+            parse_cache: Default::default(),
+            disk: Default::default(),
+        }
+    }
 }
 
 #[derive(Default)]
-struct EditorState(Mutex<Option<FileState>>);
+struct EditorState(Mutex<buffers::Buffers>);
 
 #[derive(Default)]
 struct InitialPath(Mutex<Option<(String, bool)>>); // (path, is_directory)
 
+#[derive(Default)]
+struct WorkingDirState(Mutex<Option<PathBuf>>);
+
+#[derive(Default)]
+struct WorkspaceState(Mutex<Option<PathBuf>>);
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 struct InitialPathInfo {
@@ -78,10 +188,170 @@ fn get_initial_path(state: State<InitialPath>) -> Option<InitialPathInfo> {
         })
 }
 
+fn home_dir() -> Option<String> {
+    std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()
+}
+
+fn lookup_var(name: &str) -> Result<String, String> {
+    std::env::var(name).map_err(|_| format!("unresolved variable `{}` in path", name))
+}
+
+/// Expands a leading `~`, `$VAR` / `${VAR}` and, on Windows, `%VAR%`. A `$` or `%` that
+/// doesn't start a variable name is kept literally.
+fn expand_path(input: &str) -> Result<PathBuf, String> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    if rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\") {
+        out.push_str(&home_dir().ok_or("cannot expand `~`: home directory is unknown")?);
+        rest = &rest[1..];
+    }
+
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    while let Some(pos) = rest.find(|c| c == '$' || (cfg!(windows) && c == '%')) {
+        out.push_str(&rest[..pos]);
+        let marker = &rest[pos..pos + 1];
+        let after = &rest[pos + 1..];
+
+        let (name, consumed) = if marker == "%" {
+            match after.find('%') {
+                Some(end)
+                    if end > 0
+                        && after[..end]
+                            .chars()
+                            .all(|c| is_name_char(c) || "()".contains(c)) =>
+                {
+                    (&after[..end], end + 1)
+                }
+                _ => ("", 0),
+            }
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}').ok_or("unterminated `${` in path")?;
+            (&braced[..end], end + 2)
+        } else {
+            let end = after
+                .find(|c: char| !is_name_char(c))
+                .unwrap_or(after.len());
+            (&after[..end], end)
+        };
+
+        if name.is_empty() {
+            out.push_str(marker);
+            rest = after;
+            continue;
+        }
+        out.push_str(&lookup_var(name)?);
+        rest = &after[consumed..];
+    }
+    out.push_str(rest);
+    Ok(PathBuf::from(out))
+}
+
+/// Resolves a path argument from the frontend. Absolute paths are used as-is; relative
+/// paths are joined onto the working directory set via `set_working_directory`, else the
+/// workspace root opened with `read_directory_root`, else the process working directory.
+fn resolve_path(app: &AppHandle, input: &str) -> Result<PathBuf, String> {
+    Ok(anchor_path(app, PathBuf::from(input)))
+}
+
+/// `resolve_path` for paths the user typed in. Only when nothing exists at the literal path
+/// is it run through `expand_path`, so a real `Outer$Inner.class` is left alone.
+fn resolve_typed_path(app: &AppHandle, input: &str) -> Result<PathBuf, String> {
+    let literal = resolve_path(app, input)?;
+    if literal.exists() {
+        return Ok(literal);
+    }
+    Ok(anchor_path(app, expand_path(input)?))
+}
+
+fn anchor_path(app: &AppHandle, path: PathBuf) -> PathBuf {
+    if path.is_absolute() {
+        return path;
+    }
+
+    let working_dir = app.state::<WorkingDirState>().0.lock().unwrap().clone();
+    let base = working_dir
+        .or_else(|| app.state::<WorkspaceState>().0.lock().unwrap().clone())
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join(path)
+}
+
+fn reject_traversal(path: &Path) -> Result<(), String> {
+    if path.components().any(|c| matches!(c, Component::ParentDir)) {
+        return Err("path must not contain '..' components".into());
+    }
+    Ok(())
+}
+
+// A path that doesn't exist yet is checked through its nearest existing ancestor, so
+// neither `..` nor a symlink along the way can lead out of the root. Without an open
+// workspace there is nothing to contain paths in, so every path passes.
+fn ensure_in_workspace(app: &AppHandle, path: &Path) -> Result<(), String> {
+    let root = match app.state::<WorkspaceState>().0.lock().unwrap().clone() {
+        Some(r) => canonical_path(&r),
+        None => return Ok(()),
+    };
+    let mut existing = path;
+    while !existing.exists() {
+        if existing.file_name().is_none() {
+            return Err("path outside workspace".into());
+        }
+        existing = match existing.parent() {
+            Some(parent) => parent,
+            None => return Err("path outside workspace".into()),
+        };
+    }
+    if !canonical_path(existing).starts_with(&root) {
+        return Err("path outside workspace".into());
+    }
+    Ok(())
+}
+
+/// `resolve_path` for commands that modify the filesystem.
+fn resolve_in_workspace(app: &AppHandle, input: &str) -> Result<PathBuf, String> {
+    let path = resolve_path(app, input)?;
+    ensure_in_workspace(app, &path)?;
+    Ok(path)
+}
+
+/// Canonical form of an existing path, without the `\\?\` prefix Windows adds.
+fn canonical_path(path: &Path) -> PathBuf {
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if cfg!(windows) {
+        if let Some(stripped) = canonical.to_string_lossy().strip_prefix(r"\\?\") {
+            return PathBuf::from(stripped);
+        }
+    }
+    canonical
+}
+
+#[tauri::command]
+fn set_working_directory(app: AppHandle, path: String) -> Result<String, String> {
+    let dir = resolve_typed_path(&app, &path)?;
+    if !dir.is_dir() {
+        return Err("path is not a directory".into());
+    }
+    let dir = dir.canonicalize().unwrap_or(dir);
+    *app.state::<WorkingDirState>().0.lock().unwrap() = Some(dir.clone());
+    Ok(dir.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn get_working_directory(app: AppHandle) -> Option<String> {
+    app.state::<WorkingDirState>()
+        .0
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|p| p.to_string_lossy().to_string())
+}
+
 #[tauri::command]
 async fn get_settings() -> String {
-    let local_app_data = std::env::var("LOCALAPPDATA").unwrap_or_else(|_| ".".to_string());
-    let load_dir = PathBuf::from(local_app_data).join("load");
+    let load_dir = settings::load_dir();
 
     if !load_dir.exists() {
         if let Err(e) = fs::create_dir_all(&load_dir) {
@@ -112,6 +382,8 @@ async fn ready(app: AppHandle) {
 
     println!("Client ready for commands.");
 
+    settings::watch_settings(app.clone());
+
     let pool = crate::pools::get_file_queue_pool();
     let current_files = pool.fetch_tasks();
     for file_path in current_files {
@@ -120,7 +392,10 @@ async fn ready(app: AppHandle) {
 
     tokio::spawn(async move {
         loop {
-            pool.wait_for_task();
+            let idle_ms = settings::get("task_queue_idle_ms")
+                .and_then(|v| v.as_u64())
+                .unwrap_or(DEFAULT_TASK_QUEUE_IDLE_MS);
+            pool.wait_for_task(Duration::from_millis(idle_ms.max(1)));
             let new_files = pool.fetch_tasks();
             for file_path in new_files {
                 process_queued_file(&app, &file_path).await;
@@ -129,57 +404,156 @@ async fn ready(app: AppHandle) {
     });
 }
 
+fn is_binary_content(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|b| *b == 0)
+}
+
+// Returns the decoded text and whether invalid UTF-8 had to be replaced.
+fn decode_text(bytes: Vec<u8>, allow_lossy: bool) -> Result<(String, bool), String> {
+    if is_binary_content(&bytes) {
+        return Err("file appears to be binary".into());
+    }
+    match String::from_utf8(bytes) {
+        Ok(s) => Ok((s, false)),
+        Err(e) if allow_lossy => Ok((String::from_utf8_lossy(e.as_bytes()).into_owned(), true)),
+        Err(_) => Err("file is not valid UTF-8".to_string()),
+    }
+}
+
+fn read_text_file(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    decode_text(bytes, false).map(|(s, _)| s)
+}
+
+// Accepts LF, CRLF and lone CR breaks, matching `EndingCounts::of`.
+fn split_lines(contents: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut chars = contents.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => lines.push(std::mem::take(&mut current)),
+            '\r' => {
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                lines.push(std::mem::take(&mut current));
+            }
+            _ => current.push(c),
+        }
+    }
+    lines.push(current);
+    lines
+}
+
+fn buffer_language(pb: &Path, contents: &str) -> String {
+    if settings::get_bool("detect_language_from_content", false) {
+        langguess::language_for(pb, contents)
+    } else {
+        langguess::detect_with_shebang(pb, contents)
+    }
+}
+
+fn load_file(app: &AppHandle, pb: PathBuf) -> Result<(FileMetadata, FileState), String> {
+    let bytes = fs::read(&pb).map_err(|e| e.to_string())?;
+    let (contents, lossy) = decode_text(bytes, settings::get_bool("lossy_utf8_fallback", false))?;
+    let language = buffer_language(&pb, &contents);
+
+    let mut file = FileState::from_text(pb.clone(), &contents, language, lossy);
+    if let Ok(meta) = fs::metadata(&pb) {
+        file.disk_size = meta.len();
+    }
+    file.disk = diskwatch::DiskWatch::start(app, &pb);
+    let meta = buffers::metadata(app, &file);
+    Ok((meta, file))
+}
+
 #[tauri::command]
 fn open_file(
     app: AppHandle,
     state: State<'_, EditorState>,
     path: String,
 ) -> Result<FileMetadata, String> {
-    let pb = PathBuf::from(&path);
-    let name = pb
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_string();
-    let contents = fs::read_to_string(&pb).map_err(|e| e.to_string())?;
-    let size = contents.as_bytes().len();
-    let language = detect_language_from_extension(&pb);
-    let lines: Vec<String> = contents
-        .split('\n')
-        .map(|s| s.trim_end_matches('\r').to_string())
-        .collect();
-
-    let meta = FileMetadata {
-        name: name.clone(),
-        path: path.clone(),
-        size,
-        language: language.clone(),
+    open_resolved(&app, &state, resolve_typed_path(&app, &path)?)
+}
 
-        line_count: lines.len(),
+fn open_resolved(
+    app: &AppHandle,
+    state: &EditorState,
+    pb: PathBuf,
+) -> Result<FileMetadata, String> {
+    let mut guard = state.0.lock().unwrap();
+    // An already open buffer is switched to rather than reloaded, keeping unsaved edits.
+    let meta = if guard.activate(&pb) {
+        buffers::metadata(app, guard.active().ok_or("no file opened")?)
+    } else {
+        let (meta, file) = load_file(app, pb)?;
+        guard.open(file);
+        meta
     };
 
-    {
-        let mut guard = state.0.lock().unwrap();
-        *guard = Some(FileState {
-            path: pb,
-            name,
-            size,
-            language,
-            lines,
-        });
-    }
-
     app.emit("file-opened", &meta)
         .map_err(|e| e.to_string())
         .ok();
+    if let Some(file) = guard.active_mut() {
+        emit_buffer_state(app, file);
+    }
 
     Ok(meta)
 }
 
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LineLengthInfo {
+    max_graphemes: usize,
+    max_bytes: usize,
+    /// Line holding the longest line by grapheme count.
+    line: usize,
+    max_line_length: Option<usize>,
+    over_limit: usize,
+}
+
+fn grapheme_len(line: &str) -> usize {
+    if line.is_ascii() {
+        line.len()
+    } else {
+        line.graphemes(true).count()
+    }
+}
+
+#[tauri::command]
+fn line_length_info(state: State<'_, EditorState>) -> Result<LineLengthInfo, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let limit = settings::get("max_line_length")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as usize);
+
+    let mut info = LineLengthInfo {
+        max_graphemes: 0,
+        max_bytes: 0,
+        line: 0,
+        max_line_length: limit,
+        over_limit: 0,
+    };
+    for (i, line) in file.lines.iter().enumerate() {
+        let graphemes = grapheme_len(line);
+        if graphemes > info.max_graphemes {
+            info.max_graphemes = graphemes;
+            info.line = i;
+        }
+        info.max_bytes = info.max_bytes.max(line.len());
+        if limit.is_some_and(|max| graphemes > max) {
+            info.over_limit += 1;
+        }
+    }
+    Ok(info)
+}
+
 #[tauri::command]
 fn read_line(state: State<'_, EditorState>, num: usize) -> Result<String, String> {
     let guard = state.0.lock().unwrap();
-    if let Some(file) = guard.as_ref() {
+    if let Some(file) = guard.active() {
         if num < file.lines.len() {
             Ok(file.lines[num].clone())
         } else {
@@ -198,13 +572,28 @@ fn write_line(
     content: String,
 ) -> Result<(), String> {
     let mut guard = state.0.lock().unwrap();
-    if let Some(file) = guard.as_mut() {
-        if num >= file.lines.len() {
+    if let Some(file) = guard.active_mut() {
+        let old_len = file.lines.len();
+        let splice = if num >= old_len {
             file.lines.resize(num + 1, String::new());
-        }
-        file.lines[num] = content.clone();
-        file.size =
-            file.lines.iter().map(|l| l.len()).sum::<usize>() + file.lines.len().saturating_sub(1);
+            file.lines[num] = content.clone();
+            history::LineSplice {
+                start: old_len,
+                old: Vec::new(),
+                new: file.lines[old_len..].to_vec(),
+            }
+        } else {
+            let previous = std::mem::replace(&mut file.lines[num], content.clone());
+            history::LineSplice {
+                start: num,
+                old: vec![previous],
+                new: vec![content.clone()],
+            }
+        };
+        file.history.push(vec![splice]);
+        history::emit_availability(&app, &mut file.history);
+        mark_modified(file);
+        emit_dirty(&app, file);
         app.emit(
             "file-updated",
             serde_json::json!({ "line": num, "content": content }),
@@ -225,7 +614,7 @@ fn insert_line(
     content: String,
 ) -> Result<(), String> {
     let mut guard = state.0.lock().unwrap();
-    if let Some(file) = guard.as_mut() {
+    if let Some(file) = guard.active_mut() {
         let idx = if num > file.lines.len() {
             file.lines.len()
         } else {
@@ -241,9 +630,17 @@ fn insert_line(
         } else {
             file.lines.insert(idx, content.clone());
         }
-
-        file.size =
-            file.lines.iter().map(|l| l.len()).sum::<usize>() + file.lines.len().saturating_sub(1);
+        anchors::shift_lines(file, idx, 1, 0);
+        file.history.push(vec![history::LineSplice {
+            start: idx,
+            old: Vec::new(),
+            new: vec![content.clone()],
+        }]);
+        history::emit_availability(&app, &mut file.history);
+
+        mark_modified(file);
+        emit_dirty(&app, file);
+        anchors::emit_updates(&app, file);
 
         // Emit structure change event and updated content for affected range
         app.emit(
@@ -279,16 +676,53 @@ fn insert_line(
     }
 }
 
+// Removes row `num`, or clears it when it is the buffer's only line, since a buffer
+// always keeps at least one.
+fn remove_row(file: &mut FileState, num: usize) -> Option<history::LineSplice> {
+    if num >= file.lines.len() {
+        return None;
+    }
+    let splice = if file.lines.len() == 1 {
+        history::LineSplice {
+            start: 0,
+            old: vec![std::mem::take(&mut file.lines[0])],
+            new: vec![String::new()],
+        }
+    } else {
+        let removed = file.lines.remove(num);
+        anchors::shift_lines(file, num, 0, 1);
+        history::LineSplice {
+            start: num,
+            old: vec![removed],
+            new: Vec::new(),
+        }
+    };
+    mark_modified(file);
+    Some(splice)
+}
+
 #[tauri::command]
 fn remove_line(app: AppHandle, state: State<'_, EditorState>, num: usize) -> Result<(), String> {
     let mut guard = state.0.lock().unwrap();
-    if let Some(file) = guard.as_mut() {
-        if num >= file.lines.len() {
+    if let Some(file) = guard.active_mut() {
+        let old_total = file.lines.len();
+        let splice = match remove_row(file, num) {
+            Some(s) => s,
+            None => return Ok(()),
+        };
+        file.history.push(vec![splice]);
+        history::emit_availability(&app, &mut file.history);
+        emit_dirty(&app, file);
+        if old_total == 1 {
+            app.emit(
+                "file-updated",
+                serde_json::json!({ "line": 0, "content": "", "totalLines": 1 }),
+            )
+            .map_err(|e| e.to_string())
+            .ok();
             return Ok(());
         }
-        file.lines.remove(num);
-        file.size =
-            file.lines.iter().map(|l| l.len()).sum::<usize>() + file.lines.len().saturating_sub(1);
+        anchors::emit_updates(&app, file);
 
         // Emit structure change event and updated content for affected range
         app.emit(
@@ -324,66 +758,51 @@ fn remove_line(app: AppHandle, state: State<'_, EditorState>, num: usize) -> Res
     }
 }
 
+// Orders a requested row range and clamps it to the buffer, so reversed or
+// out-of-bounds requests from the view still yield a valid inclusive range.
+fn clamp_line_range(line_count: usize, line_start: usize, line_end: usize) -> (usize, usize) {
+    let last = line_count.saturating_sub(1);
+    let (lo, hi) = if line_start <= line_end {
+        (line_start, line_end)
+    } else {
+        (line_end, line_start)
+    };
+    (lo.min(last), hi.min(last))
+}
+
+/// Rows (inclusive) the parser is restricted to in large-file mode.
+#[derive(Clone, Deserialize)]
+struct ParseWindow {
+    start: usize,
+    end: usize,
+}
+
 #[tauri::command]
 fn request_tokenization(
     app: AppHandle,
     state: State<'_, EditorState>,
     line_start: usize,
     line_end: usize,
+    window: Option<ParseWindow>,
 ) -> Result<(), String> {
     let guard = state.0.lock().unwrap();
 
-    if let Some(file) = guard.as_ref() {
-        if file.lines.is_empty() {
-            app.emit("tokenization", Vec::<Token>::new())
-                .map_err(|e| e.to_string())
-                .ok();
-
-            return Ok(());
-        }
-
-        let start = line_start.min(file.lines.len().saturating_sub(1));
-        let end = line_end.min(file.lines.len().saturating_sub(1));
-
-        let text = file.lines.join("\n");
-        let mut tokens: Vec<Token> = Vec::new();
-
-        if let Some(lang) = get_ts_language(&file.language) {
-            let mut parser = Parser::new();
-            if parser.set_language(&lang).is_ok() {
-                if let Some(tree) = parser.parse(&text, None) {
-                    let mut raw: Vec<(Point, Point, String)> = Vec::new();
-                    collect_ts_tokens(tree.root_node(), start, end, &mut raw);
-                    for (sp, ep, kind) in raw {
-                        if ep.row < start || sp.row > end {
-                            continue;
-                        }
-                        tokens.push(Token {
-                            start_offset: Offset {
-                                row: sp.row,
-                                col: sp.column,
-                            },
-                            end_offset: Offset {
-                                row: ep.row,
-                                col: ep.column,
-                            },
-                            kind,
-                        });
-                    }
-                }
-            }
-        }
+    if let Some(file) = guard.active() {
+        let (start, end) = clamp_line_range(file.lines.len(), line_start, line_end);
 
-        if tokens.is_empty() {
-            for row in start..=end {
-                let len = file.lines[row].len();
-                tokens.push(Token {
-                    start_offset: Offset { row, col: 0 },
-                    end_offset: Offset { row, col: len },
-                    kind: "untokenized".to_string(),
-                });
+        let tokens = match window {
+            Some(w) => {
+                let (w_start, w_end) = clamp_line_range(file.lines.len(), w.start, w.end);
+                tokenize_window(&file.language, &file.lines, w_start, w_end, start, end)
             }
-        }
+            None => tokenize_range(
+                &file.language,
+                parse_buffer(file).as_ref(),
+                &file.lines,
+                start,
+                end,
+            ),
+        };
 
         app.emit("tokenization", &tokens)
             .map_err(|e| e.to_string())
@@ -395,17 +814,210 @@ fn request_tokenization(
 }
 
 #[tauri::command]
-fn save_buffer(state: State<'_, EditorState>) -> Result<(), String> {
+fn tokenize_path(
+    app: AppHandle,
+    path: String,
+    line_start: usize,
+    line_end: usize,
+) -> Result<Vec<Token>, String> {
+    let pb = resolve_path(&app, &path)?;
+    let contents = read_text_file(&pb)?;
+    let lines = split_lines(&contents);
+    let language = detect_language_from_extension(&pb);
+
+    let (start, end) = clamp_line_range(lines.len(), line_start, line_end);
+    let tree = parse_text(&language, &contents);
+    Ok(tokenize_range(&language, tree.as_ref(), &lines, start, end))
+}
+
+// Every mutation funnels through here so buffers never end up with zero lines.
+fn mark_modified(file: &mut FileState) {
+    if file.lines.is_empty() {
+        file.lines.push(String::new());
+    }
+    file.size =
+        file.lines.iter().map(|l| l.len()).sum::<usize>() + file.lines.len().saturating_sub(1);
+    file.dirty = true;
+    file.version += 1;
+}
+
+/// Emits `dirty-changed` when the buffer's dirty flag differs from what the UI was last told.
+fn emit_dirty(app: &AppHandle, file: &mut FileState) {
+    if file.dirty == file.dirty_reported {
+        return;
+    }
+    file.dirty_reported = file.dirty;
+    app.emit(
+        "dirty-changed",
+        serde_json::json!({ "path": file.path.to_string_lossy(), "dirty": file.dirty }),
+    )
+    .map_err(|e| e.to_string())
+    .ok();
+}
+
+/// Re-sends a buffer's dirty flag and undo/redo availability once it becomes active, since
+/// what the UI was last told belongs to the previous buffer.
+fn emit_buffer_state(app: &AppHandle, file: &mut FileState) {
+    file.dirty_reported = !file.dirty;
+    emit_dirty(app, file);
+    history::announce_availability(app, &mut file.history);
+}
+
+// A symlink is written through to its target, and the target keeps its permissions, so
+// replacing the file doesn't turn a link into a regular file or drop an executable bit.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let target = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    let file_name = target
+        .file_name()
+        .and_then(|s| s.to_str())
+        .ok_or("invalid file path")?;
+    let tmp = target.with_file_name(format!(".{}.load-tmp", file_name));
+    fs::write(&tmp, contents).map_err(|e| e.to_string())?;
+    if let Ok(meta) = fs::metadata(&target) {
+        if let Err(e) = fs::set_permissions(&tmp, meta.permissions()) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e.to_string());
+        }
+    }
+    fs::rename(&tmp, &target).map_err(|e| {
+        let _ = fs::remove_file(&tmp);
+        e.to_string()
+    })
+}
+
+fn save_file_state(file: &mut FileState) -> Result<(), String> {
+    if file.lossy {
+        return Err("file was decoded lossily; confirm before saving".into());
+    }
+    let (contents, ending_counts) = endings::saved_text(file);
+    backups::write_backup(&file.path);
+    file.disk
+        .guard_write(&file.path, || write_atomic(&file.path, contents.as_bytes()))?;
+    file.disk_size = fs::metadata(&file.path)
+        .map(|m| m.len())
+        .unwrap_or(contents.len() as u64);
+    file.ending_counts = ending_counts;
+    file.dirty = false;
+    Ok(())
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SaveReport {
+    /// Set only with `warn_on_save_with_errors`; the file was saved regardless.
+    has_syntax_errors: bool,
+}
+
+#[tauri::command]
+fn save_buffer(app: AppHandle, state: State<'_, EditorState>) -> Result<SaveReport, String> {
+    let mut guard = state.0.lock().unwrap();
+    if let Some(file) = guard.active_mut() {
+        save_file_state(file)?;
+        emit_dirty(&app, file);
+        Ok(SaveReport {
+            has_syntax_errors: settings::get_bool("warn_on_save_with_errors", false)
+                && syntax::has_parse_errors(file),
+        })
+    } else {
+        Err("no file opened".to_string())
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum SaveOutcome {
+    NoChanges,
+    Appended,
+    Written,
+}
+
+// Skips the write when the disk already matches and appends when the buffer only grew at the
+// end, which never touches the bytes already on disk; anything else goes through the
+// regular atomic save.
+#[tauri::command]
+fn save_buffer_incremental(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+) -> Result<SaveOutcome, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    let outcome = write_incremental(file)?;
+    emit_dirty(&app, file);
+    Ok(outcome)
+}
+
+fn write_incremental(file: &mut FileState) -> Result<SaveOutcome, String> {
+    if file.lossy {
+        return Err("file was decoded lossily; confirm before saving".into());
+    }
+
+    let (contents, ending_counts) = endings::saved_text(file);
+    let on_disk = match fs::read(&file.path) {
+        Ok(b) => b,
+        Err(_) => {
+            save_file_state(file)?;
+            return Ok(SaveOutcome::Written);
+        }
+    };
+
+    if on_disk == contents.as_bytes() {
+        file.disk.record(&file.path);
+        file.disk_size = on_disk.len() as u64;
+        file.ending_counts = ending_counts;
+        file.dirty = false;
+        return Ok(SaveOutcome::NoChanges);
+    }
+
+    if !on_disk.is_empty() && contents.as_bytes().starts_with(&on_disk) {
+        backups::write_backup(&file.path);
+        file.disk.guard_write(&file.path, || {
+            fs::OpenOptions::new()
+                .append(true)
+                .open(&file.path)
+                .and_then(|mut out| {
+                    out.write_all(&contents.as_bytes()[on_disk.len()..])?;
+                    out.sync_all()
+                })
+                .map_err(|e| e.to_string())
+        })?;
+        file.disk_size = contents.len() as u64;
+        file.ending_counts = ending_counts;
+        file.dirty = false;
+        return Ok(SaveOutcome::Appended);
+    }
+
+    save_file_state(file)?;
+    Ok(SaveOutcome::Written)
+}
+
+#[tauri::command]
+fn is_dirty(state: State<'_, EditorState>) -> Result<bool, String> {
     let guard = state.0.lock().unwrap();
-    if let Some(file) = guard.as_ref() {
-        let contents = file.lines.join("\n");
-        fs::write(&file.path, contents).map_err(|e| e.to_string())?;
+    let file = guard.active().ok_or("no file opened")?;
+    Ok(file.dirty)
+}
+
+#[tauri::command]
+fn confirm_lossy_save(state: State<'_, EditorState>) -> Result<(), String> {
+    let mut guard = state.0.lock().unwrap();
+    if let Some(file) = guard.active_mut() {
+        file.lossy = false;
         Ok(())
     } else {
         Err("no file opened".to_string())
     }
 }
 
+// Filtered through `get_ts_language` so the picker never offers a name without a grammar.
+#[tauri::command]
+fn supported_languages() -> Vec<String> {
+    TS_LANGUAGES
+        .iter()
+        .filter(|l| get_ts_language(l).is_some())
+        .map(|l| l.to_string())
+        .collect()
+}
+
 #[tauri::command]
 fn change_language(
     app: AppHandle,
@@ -413,8 +1025,9 @@ fn change_language(
     language: String,
 ) -> Result<(), String> {
     let mut guard = state.0.lock().unwrap();
-    if let Some(file) = guard.as_mut() {
+    if let Some(file) = guard.active_mut() {
         file.language = language.clone();
+        file.parse_cache.get_mut().take();
         app.emit(
             "language-changed",
             serde_json::json!({ "language": language }),
@@ -427,36 +1040,143 @@ fn change_language(
     }
 }
 
-#[tauri::command]
-fn close_file(state: State<'_, EditorState>) -> Result<(), String> {
-    let mut guard = state.0.lock().unwrap();
-    *guard = None;
-    Ok(())
-}
+const MAX_CLOSED_BUFFERS: usize = 20;
 
-#[derive(Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct DirEntryItem {
-    name: String,
-    path: String,
-    isDir: bool,
-    ignored: bool,
-    children: Option<Vec<DirEntryItem>>,
+struct ClosedBuffer {
+    path: PathBuf,
+    cursor: Option<Offset>,
+    scroll: Option<f64>,
+    /// Unsaved contents and their line breaks, kept only when the buffer was dirty.
+    lines: Option<(Vec<String>, Vec<endings::LineEnding>)>,
 }
 
+#[derive(Default)]
+struct ClosedBuffers(Mutex<VecDeque<ClosedBuffer>>);
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct PathMovedPayload {
-    src: String,
-    dest: String,
-}
-
-fn is_dot_folder(name: &str) -> bool {
-    name.starts_with('.')
+struct ReopenedFile {
+    #[serde(flatten)]
+    meta: FileMetadata,
+    cursor: Option<Offset>,
+    scroll: Option<f64>,
 }
 
-fn build_gitignore(root: &Path) -> Option<Gitignore> {
-    let mut builder = GitignoreBuilder::new(root);
+// Closes `path`, or the active buffer when no path is given. If that was the active one,
+// the buffer that takes its place is announced through `file-opened`.
+#[tauri::command]
+fn close_file(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    closed: State<'_, ClosedBuffers>,
+    path: Option<String>,
+    cursor: Option<Offset>,
+    scroll: Option<f64>,
+) -> Result<(), String> {
+    let mut guard = state.0.lock().unwrap();
+    let target = match path {
+        Some(p) => resolve_path(&app, &p)?,
+        None => match guard.active() {
+            Some(f) => f.path.clone(),
+            None => return Ok(()),
+        },
+    };
+    let was_active = guard.active().is_some_and(|f| f.path == target);
+    if let Some(file) = guard.close(&target) {
+        let mut stack = closed.0.lock().unwrap();
+        stack.push_back(ClosedBuffer {
+            path: file.path,
+            cursor,
+            scroll,
+            lines: file.dirty.then_some((file.lines, file.breaks)),
+        });
+        while stack.len() > MAX_CLOSED_BUFFERS {
+            stack.pop_front();
+        }
+    }
+    if let Some(next) = guard.active_mut().filter(|_| was_active) {
+        app.emit("file-opened", buffers::metadata(&app, next))
+            .map_err(|e| e.to_string())
+            .ok();
+        emit_buffer_state(&app, next);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn reopen_last_closed(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    closed: State<'_, ClosedBuffers>,
+) -> Result<Option<ReopenedFile>, String> {
+    let entry = match closed.0.lock().unwrap().pop_back() {
+        Some(e) => e,
+        None => return Ok(None),
+    };
+    let mut guard = state.0.lock().unwrap();
+    // Opened again since it was closed: that live buffer wins over the remembered lines.
+    let meta = if guard.activate(&entry.path) {
+        buffers::metadata(&app, guard.active().ok_or("no file opened")?)
+    } else {
+        let (mut meta, mut file) = match load_file(&app, entry.path.clone()) {
+            Ok(v) => v,
+            Err(e) => {
+                closed.0.lock().unwrap().push_back(entry);
+                return Err(e);
+            }
+        };
+        if let Some((lines, breaks)) = entry.lines {
+            file.lines = lines;
+            file.breaks = breaks;
+            mark_modified(&mut file);
+            meta.size = file.size;
+            meta.line_count = file.lines.len();
+        }
+        guard.open(file);
+        meta
+    };
+
+    app.emit("file-opened", &meta)
+        .map_err(|e| e.to_string())
+        .ok();
+    if let Some(file) = guard.active_mut() {
+        emit_buffer_state(&app, file);
+    }
+
+    Ok(Some(ReopenedFile {
+        meta,
+        cursor: entry.cursor,
+        scroll: entry.scroll,
+    }))
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DirEntryItem {
+    name: String,
+    path: String,
+    isDir: bool,
+    ignored: bool,
+    children: Option<Vec<DirEntryItem>>,
+    has_children: Option<bool>,
+    /// Files only: length in bytes and modification time in milliseconds since the epoch.
+    size: Option<u64>,
+    modified: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PathMovedPayload {
+    src: String,
+    dest: String,
+}
+
+fn is_dot_folder(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+fn build_gitignore(root: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(root);
 
     let gi_path = root.join(".gitignore");
     if gi_path.exists() {
@@ -474,6 +1194,48 @@ fn build_gitignore(root: &Path) -> Option<Gitignore> {
     }
 }
 
+/// The root ignore rules plus the `.gitignore` of every directory from the root down to
+/// the one being listed, so a nested file can add to or override its parents like in git.
+struct IgnoreLayers {
+    layers: Vec<Gitignore>,
+}
+
+impl IgnoreLayers {
+    fn for_dir(root: &Path, dir: &Path) -> Self {
+        let mut layers: Vec<Gitignore> = build_gitignore(root).into_iter().collect();
+        if let Ok(rel) = dir.strip_prefix(root) {
+            let mut current = root.to_path_buf();
+            for component in rel.components() {
+                current.push(component);
+                let gi_path = current.join(".gitignore");
+                if !gi_path.is_file() {
+                    continue;
+                }
+                let mut builder = GitignoreBuilder::new(&current);
+                let _ = builder.add(gi_path);
+                if let Ok(m) = builder.build() {
+                    layers.push(m);
+                }
+            }
+        }
+        IgnoreLayers { layers }
+    }
+
+    // The deepest layer with an opinion decides, so a nested `!pattern` can re-include.
+    fn ignores(&self, path: &Path, is_dir: bool) -> bool {
+        for layer in self.layers.iter().rev() {
+            let matched = layer.matched(path, is_dir);
+            if matched.is_ignore() {
+                return true;
+            }
+            if matched.is_whitelist() {
+                return false;
+            }
+        }
+        false
+    }
+}
+
 fn is_ignored_path(m: Option<&Gitignore>, root: &Path, path: &Path, is_dir: bool) -> bool {
     if let Some(matcher) = m {
         if let Ok(rel) = path.strip_prefix(root) {
@@ -486,11 +1248,82 @@ fn is_ignored_path(m: Option<&Gitignore>, root: &Path, path: &Path, is_dir: bool
     false
 }
 
-fn build_dir_entry(
-    path: &Path,
+/// Calls `visit` for every file under `root` that isn't gitignored, without descending into
+/// dot-folders. The walk stops as soon as `visit` returns false.
+fn walk_files(root: &Path, mut visit: impl FnMut(&Path) -> bool) {
+    let matcher = build_gitignore(root);
+    walk_dir(root, root, matcher.as_ref(), &mut visit);
+}
+
+fn walk_dir(
     root: &Path,
+    dir: &Path,
     matcher: Option<&Gitignore>,
-) -> Result<DirEntryItem, String> {
+    visit: &mut impl FnMut(&Path) -> bool,
+) -> bool {
+    let rd = match fs::read_dir(dir) {
+        Ok(v) => v,
+        Err(_) => return true,
+    };
+    for entry in rd.flatten() {
+        let ft = match entry.file_type() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+        if ft.is_dir() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if is_dot_folder(&name) || is_ignored_path(matcher, root, &path, true) {
+                continue;
+            }
+            if !walk_dir(root, &path, matcher, visit) {
+                return false;
+            }
+        } else if ft.is_file() && !is_ignored_path(matcher, root, &path, false) && !visit(&path) {
+            return false;
+        }
+    }
+    true
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceMembership {
+    within: bool,
+    /// The workspace root containing the path, when it is inside one.
+    root: Option<String>,
+}
+
+#[tauri::command]
+fn is_within_workspace(app: AppHandle, path: String) -> Result<WorkspaceMembership, String> {
+    let pb = resolve_path(&app, &path)?;
+    let root = app.state::<WorkspaceState>().0.lock().unwrap().clone();
+    let root = root.filter(|root| canonical_path(&pb).starts_with(canonical_path(root)));
+    Ok(WorkspaceMembership {
+        within: root.is_some(),
+        root: root.map(|r| r.to_string_lossy().to_string()),
+    })
+}
+
+// Unlike the per-entry tree check, this also honors ignored parent directories.
+fn workspace_ignores(app: &AppHandle, path: &Path) -> bool {
+    let root = match app.state::<WorkspaceState>().0.lock().unwrap().clone() {
+        Some(r) => r,
+        None => return false,
+    };
+    let rel = match path.strip_prefix(&root) {
+        Ok(r) => r,
+        Err(_) => return false,
+    };
+    match build_gitignore(&root) {
+        Some(matcher) => matcher
+            .matched_path_or_any_parents(rel, path.is_dir())
+            .is_ignore(),
+        None => false,
+    }
+}
+
+fn build_dir_entry(path: &Path, ignores: &IgnoreLayers) -> Result<DirEntryItem, String> {
     let is_dir = path.is_dir();
 
     let name = {
@@ -500,7 +1333,13 @@ fn build_dir_entry(
             .unwrap_or_else(|| path.display().to_string())
     };
 
-    let ignored = is_ignored_path(matcher, root, path, is_dir);
+    let ignored = ignores.ignores(path, is_dir);
+    let meta = fs::metadata(path).ok().filter(|m| m.is_file());
+    let modified = meta
+        .as_ref()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64);
 
     Ok(DirEntryItem {
         name,
@@ -508,13 +1347,21 @@ fn build_dir_entry(
         isDir: is_dir,
         ignored,
         children: None,
+        // Only the first entry is read, so the tree can draw an expander without listing.
+        has_children: is_dir.then(|| {
+            fs::read_dir(path)
+                .map(|mut rd| rd.next().is_some())
+                .unwrap_or(false)
+        }),
+        size: meta.map(|m| m.len()),
+        modified,
     })
 }
 
 fn list_dir_children(
     dir: &Path,
-    root: &Path,
-    matcher: Option<&Gitignore>,
+    ignores: &IgnoreLayers,
+    show_hidden: bool,
 ) -> Result<Vec<DirEntryItem>, String> {
     let mut children: Vec<DirEntryItem> = Vec::new();
 
@@ -532,12 +1379,12 @@ fn list_dir_children(
         };
         let child_name = entry.file_name().to_string_lossy().to_string();
 
-        // Hide any folder that starts with a '.'
-        if ft.is_dir() && is_dot_folder(&child_name) {
+        // Hide any folder that starts with a '.' unless asked to show them
+        if !show_hidden && ft.is_dir() && is_dot_folder(&child_name) {
             continue;
         }
 
-        match build_dir_entry(&child_path, root, matcher) {
+        match build_dir_entry(&child_path, ignores) {
             Ok(child) => children.push(child),
             Err(_) => continue,
         }
@@ -555,25 +1402,63 @@ fn list_dir_children(
 }
 
 #[tauri::command]
-fn read_directory_root(path: String) -> Result<DirEntryItem, String> {
-    let root = PathBuf::from(&path);
+fn read_directory_root(
+    app: AppHandle,
+    path: String,
+    show_hidden: Option<bool>,
+) -> Result<DirEntryItem, String> {
+    let root = resolve_path(&app, &path)?;
     if !root.exists() {
         return Err("path does not exist".into());
     }
     if !root.is_dir() {
         return Err("path is not a directory".into());
     }
-    let matcher = build_gitignore(&root);
+    let ignores = IgnoreLayers::for_dir(&root, &root);
 
-    let mut node = build_dir_entry(&root, &root, matcher.as_ref())?;
-    let children = list_dir_children(&root, &root, matcher.as_ref())?;
+    let mut node = build_dir_entry(&root, &ignores)?;
+    let children = list_dir_children(&root, &ignores, show_hidden.unwrap_or(false))?;
     node.children = Some(children);
+    settings::load_workspace(&root);
+    settings::watch_workspace(app.clone(), &root);
+    *app.state::<WorkspaceState>().0.lock().unwrap() = Some(root);
     Ok(node)
 }
 
+// Paths are built from `root` as given so they match the entries `read_directory_root`
+// produced; containment is checked on the canonical forms so symlinks and `\\?\` agree.
 #[tauri::command]
-fn read_directory_children(path: String, root: String) -> Result<Vec<DirEntryItem>, String> {
-    let dir = PathBuf::from(&path);
+fn path_ancestors_within_root(
+    app: AppHandle,
+    path: String,
+    root: String,
+) -> Result<Vec<String>, String> {
+    let root_pb = resolve_path(&app, &root)?;
+    let pb = resolve_path(&app, &path)?;
+    let rel = canonical_path(&pb)
+        .strip_prefix(canonical_path(&root_pb))
+        .map(|r| r.to_path_buf())
+        .map_err(|_| format!("{} is not inside {}", pb.display(), root_pb.display()))?;
+
+    let mut dir = root_pb;
+    let mut ancestors = vec![dir.to_string_lossy().to_string()];
+    let mut components: Vec<_> = rel.components().collect();
+    components.pop();
+    for component in components {
+        dir.push(component);
+        ancestors.push(dir.to_string_lossy().to_string());
+    }
+    Ok(ancestors)
+}
+
+#[tauri::command]
+fn read_directory_children(
+    app: AppHandle,
+    path: String,
+    root: String,
+    show_hidden: Option<bool>,
+) -> Result<Vec<DirEntryItem>, String> {
+    let dir = resolve_path(&app, &path)?;
     if !dir.exists() {
         return Err("path does not exist".into());
     }
@@ -581,13 +1466,168 @@ fn read_directory_children(path: String, root: String) -> Result<Vec<DirEntryIte
         return Err("path is not a directory".into());
     }
 
-    let root_pb = PathBuf::from(&root);
-    let matcher = build_gitignore(&root_pb);
+    let root_pb = resolve_path(&app, &root)?;
+    let ignores = IgnoreLayers::for_dir(&root_pb, &dir);
+
+    list_dir_children(&dir, &ignores, show_hidden.unwrap_or(false))
+}
+
+const DEFAULT_DIR_PAGE_SIZE: usize = 500;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DirPage {
+    entries: Vec<DirEntryItem>,
+    /// Pass back as `cursor` for the following page; `None` after the last one.
+    next_cursor: Option<String>,
+}
+
+// Same order as `list_dir_children`: directories first, then case-insensitive name.
+type DirSortKey = (bool, String, String); // (is file, lowercased name, name)
+
+fn dir_cursor(key: &DirSortKey) -> String {
+    format!("{}:{}", if key.0 { "f" } else { "d" }, key.2)
+}
+
+fn parse_dir_cursor(cursor: &str) -> Result<DirSortKey, String> {
+    let (kind, name) = cursor.split_once(':').ok_or("invalid directory cursor")?;
+    let is_file = match kind {
+        "f" => true,
+        "d" => false,
+        _ => return Err("invalid directory cursor".into()),
+    };
+    Ok((is_file, name.to_lowercase(), name.to_string()))
+}
+
+// Streams the directory keeping only the `page_size + 1` smallest keys past the cursor,
+// so memory stays bounded no matter how many entries the directory has. The cursor is
+// the last key returned rather than a position, so entries added or removed elsewhere
+// in the listing don't shift later pages.
+#[tauri::command]
+fn list_dir_page(
+    app: AppHandle,
+    dir: String,
+    root: String,
+    cursor: Option<String>,
+    page_size: Option<usize>,
+    show_hidden: Option<bool>,
+) -> Result<DirPage, String> {
+    let dir_pb = resolve_path(&app, &dir)?;
+    if !dir_pb.is_dir() {
+        return Err("path is not a directory".into());
+    }
+    let root_pb = resolve_path(&app, &root)?;
+    let after = cursor.as_deref().map(parse_dir_cursor).transpose()?;
+    let page_size = page_size.unwrap_or(DEFAULT_DIR_PAGE_SIZE).max(1);
+    let show_hidden = show_hidden.unwrap_or(false);
+
+    let mut heap: BinaryHeap<DirSortKey> = BinaryHeap::new();
+    for entry in fs::read_dir(&dir_pb).map_err(|e| e.to_string())?.flatten() {
+        let is_dir = match entry.file_type() {
+            Ok(ft) => ft.is_dir(),
+            Err(_) => continue,
+        };
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_dir && !show_hidden && is_dot_folder(&name) {
+            continue;
+        }
+        let key = (!is_dir, name.to_lowercase(), name);
+        if after.as_ref().is_some_and(|a| &key <= a) {
+            continue;
+        }
+        if heap.len() <= page_size {
+            heap.push(key);
+        } else if heap.peek().is_some_and(|max| &key < max) {
+            heap.pop();
+            heap.push(key);
+        }
+    }
+
+    let mut keys = heap.into_sorted_vec();
+    let has_more = keys.len() > page_size;
+    keys.truncate(page_size);
+    let ignores = IgnoreLayers::for_dir(&root_pb, &dir_pb);
+    let entries = keys
+        .iter()
+        .filter_map(|k| build_dir_entry(&dir_pb.join(&k.2), &ignores).ok())
+        .collect();
+    Ok(DirPage {
+        entries,
+        next_cursor: if has_more {
+            keys.last().map(dir_cursor)
+        } else {
+            None
+        },
+    })
+}
+
+/// An entry as the frontend last saw it, in the shape `DirEntryItem` is sent in.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KnownEntry {
+    path: String,
+    is_dir: bool,
+    ignored: bool,
+    #[serde(default)]
+    size: Option<u64>,
+    #[serde(default)]
+    modified: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TreeDelta {
+    added: Vec<DirEntryItem>,
+    removed: Vec<String>,
+    /// Entries still present whose kind, ignore status, size or mtime differs from what
+    /// was known.
+    changed: Vec<DirEntryItem>,
+}
 
-    list_dir_children(&dir, &root_pb, matcher.as_ref())
+#[tauri::command]
+fn refresh_directory(
+    app: AppHandle,
+    path: String,
+    root: String,
+    known: Vec<KnownEntry>,
+    show_hidden: Option<bool>,
+) -> Result<TreeDelta, String> {
+    let fresh = read_directory_children(app, path, root, show_hidden)?;
+    let mut known: HashMap<String, KnownEntry> =
+        known.into_iter().map(|k| (k.path.clone(), k)).collect();
+
+    let mut delta = TreeDelta {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+    for entry in fresh {
+        match known.remove(&entry.path) {
+            None => delta.added.push(entry),
+            Some(k)
+                if k.is_dir != entry.isDir
+                    || k.ignored != entry.ignored
+                    || k.size != entry.size
+                    || k.modified != entry.modified =>
+            {
+                delta.changed.push(entry)
+            }
+            Some(_) => {}
+        }
+    }
+    delta.removed = known.into_keys().collect();
+    delta.removed.sort();
+    Ok(delta)
 }
 
 fn detect_language_from_extension(path: &PathBuf) -> String {
+    let name = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+    match name {
+        "Dockerfile" | "Containerfile" => return "dockerfile".into(),
+        "Makefile" | "makefile" | "GNUmakefile" => return "make".into(),
+        "CMakeLists.txt" => return "cmake".into(),
+        _ => {}
+    }
     match path
         .extension()
         .and_then(|s| s.to_str())
@@ -619,11 +1659,41 @@ fn detect_language_from_extension(path: &PathBuf) -> String {
         "ml" | "mli" => "ocaml".into(),
         "sh" | "bash" => "bash".into(),
         "ps1" | "psm1" | "psd1" => "powershell".into(),
+        "dockerfile" => "dockerfile".into(),
+        "mk" => "make".into(),
+        "cmake" => "cmake".into(),
 
         other => other.to_string(),
     }
 }
 
+/// Every identifier `get_ts_language` has a grammar for, in picker order.
+const TS_LANGUAGES: &[&str] = &[
+    "bash",
+    "c",
+    "cpp",
+    "css",
+    "dart",
+    "go",
+    "haskell",
+    "html",
+    "java",
+    "javascript",
+    "json",
+    "lua",
+    "markdown",
+    "ocaml",
+    "php",
+    "powershell",
+    "python",
+    "ruby",
+    "rust",
+    "swift",
+    "tsx",
+    "typescript",
+    "zig",
+];
+
 fn get_ts_language(language: &str) -> Option<Language> {
     match language.to_ascii_lowercase().as_str() {
         "rust" => Some(tree_sitter_rust::LANGUAGE.into()),
@@ -653,6 +1723,243 @@ fn get_ts_language(language: &str) -> Option<Language> {
     }
 }
 
+fn parse_text(language: &str, text: &str) -> Option<Tree> {
+    let lang = get_ts_language(language)?;
+    let mut parser = Parser::new();
+    parser.set_language(&lang).ok()?;
+    parser.parse(text, None)
+}
+
+/// The last tree parsed for a buffer along with the text, language and buffer version it
+/// came from.
+struct ParseCache {
+    language: String,
+    version: u64,
+    source: String,
+    tree: Tree,
+}
+
+fn point_at(text: &str, byte: usize) -> Point {
+    let before = &text.as_bytes()[..byte];
+    let row = before.iter().filter(|b| **b == b'\n').count();
+    let line_start = before
+        .iter()
+        .rposition(|b| *b == b'\n')
+        .map_or(0, |i| i + 1);
+    Point {
+        row,
+        column: byte - line_start,
+    }
+}
+
+// The changed span is whatever lies between the longest common prefix and suffix of the
+// old and new text, so every kind of buffer mutation is covered without each command
+// having to report its own byte offsets.
+fn input_edit(old: &str, new: &str) -> Option<InputEdit> {
+    let (a, b) = (old.as_bytes(), new.as_bytes());
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    if prefix == a.len() && prefix == b.len() {
+        return None;
+    }
+    let max_suffix = a.len().min(b.len()) - prefix;
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take(max_suffix)
+        .take_while(|(x, y)| x == y)
+        .count();
+    Some(InputEdit {
+        start_byte: prefix,
+        old_end_byte: a.len() - suffix,
+        new_end_byte: b.len() - suffix,
+        start_position: point_at(old, prefix),
+        old_end_position: point_at(old, a.len() - suffix),
+        new_end_position: point_at(new, b.len() - suffix),
+    })
+}
+
+// Reparses incrementally against the cached tree when the language is unchanged. An
+// unedited buffer reuses the cached tree without joining its lines again.
+fn parse_buffer(file: &FileState) -> Option<Tree> {
+    let lang = get_ts_language(&file.language)?;
+    let mut cache = file.parse_cache.borrow_mut();
+    if let Some(c) = cache.as_mut() {
+        if c.language == file.language && c.version == file.version {
+            return Some(c.tree.clone());
+        }
+    }
+    let source = file.lines.join("\n");
+    let old_tree = match cache.take() {
+        Some(c) if c.language == file.language => {
+            if c.source == source {
+                let tree = c.tree.clone();
+                *cache = Some(ParseCache {
+                    version: file.version,
+                    ..c
+                });
+                return Some(tree);
+            }
+            let mut tree = c.tree;
+            if let Some(edit) = input_edit(&c.source, &source) {
+                tree.edit(&edit);
+            }
+            Some(tree)
+        }
+        _ => None,
+    };
+
+    let mut parser = Parser::new();
+    parser.set_language(&lang).ok()?;
+    let tree = parser.parse(&source, old_tree.as_ref())?;
+    *cache = Some(ParseCache {
+        language: file.language.clone(),
+        version: file.version,
+        source,
+        tree: tree.clone(),
+    });
+    Some(tree)
+}
+
+// Hands the parser the rest of the line at `point`, or the break after it, so the
+// buffer is read in place rather than through a joined copy.
+fn line_chunk(lines: &[String], point: Point) -> &[u8] {
+    match lines.get(point.row) {
+        Some(line) if point.column < line.len() => &line.as_bytes()[point.column..],
+        Some(_) if point.row + 1 < lines.len() => b"\n",
+        _ => &[],
+    }
+}
+
+// Parses only rows `start..=end`; node positions stay relative to the whole buffer.
+fn parse_rows(language: &str, lines: &[String], start: usize, end: usize) -> Option<Tree> {
+    let lang = get_ts_language(language)?;
+    let mut parser = Parser::new();
+    parser.set_language(&lang).ok()?;
+
+    let row_byte = |row: usize| lines[..row].iter().map(|l| l.len() + 1).sum::<usize>();
+    let end_len = lines.get(end).map(|l| l.len()).unwrap_or(0);
+    let range = Range {
+        start_byte: row_byte(start),
+        end_byte: row_byte(end) + end_len,
+        start_point: Point {
+            row: start,
+            column: 0,
+        },
+        end_point: Point {
+            row: end,
+            column: end_len,
+        },
+    };
+    parser.set_included_ranges(&[range]).ok()?;
+    parser.parse_with_options(&mut |_, point| line_chunk(lines, point), None, None)
+}
+
+// Errors touching the window's first or last row usually come from a construct cut in
+// half by the window, so their rows can't be trusted.
+fn collect_edge_error_rows(
+    node: tree_sitter::Node,
+    start: usize,
+    end: usize,
+    rows: &mut BTreeSet<usize>,
+) {
+    if !node.has_error() {
+        return;
+    }
+    if node.is_error() || node.is_missing() {
+        let (first, last) = (node.start_position().row, node.end_position().row);
+        if first <= start || last >= end {
+            rows.extend(first.max(start)..=last.min(end));
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_edge_error_rows(child, start, end, rows);
+    }
+}
+
+fn tokenize_window(
+    language: &str,
+    lines: &[String],
+    window_start: usize,
+    window_end: usize,
+    start: usize,
+    end: usize,
+) -> Vec<Token> {
+    if start > window_end || end < window_start {
+        return tokenize_range(language, None, lines, start, end);
+    }
+    let (start, end) = (start.max(window_start), end.min(window_end));
+    let tree = parse_rows(language, lines, window_start, window_end);
+    let mut tokens = tokenize_range(language, tree.as_ref(), lines, start, end);
+
+    let mut edge_rows = BTreeSet::new();
+    if let Some(tree) = &tree {
+        collect_edge_error_rows(tree.root_node(), window_start, window_end, &mut edge_rows);
+    }
+    if edge_rows.is_empty() {
+        return tokens;
+    }
+    tokens.retain(|t| {
+        !edge_rows.contains(&t.start_offset.row) && !edge_rows.contains(&t.end_offset.row)
+    });
+    for row in edge_rows.range(start..=end) {
+        tokens.extend(tokenize_range(language, None, lines, *row, *row));
+    }
+    tokens.sort_by_key(|t| (t.start_offset.row, t.start_offset.col));
+    tokens
+}
+
+// Tokens carry highlight scopes from the language's highlight query; languages without
+// one fall back to the raw leaf node kinds.
+fn tokenize_range(
+    language: &str,
+    tree: Option<&Tree>,
+    lines: &[String],
+    start: usize,
+    end: usize,
+) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+
+    if let Some(tree) = tree {
+        let raw = highlight::query_tokens(language, tree, lines, start, end).unwrap_or_else(|| {
+            let mut raw = Vec::new();
+            collect_ts_tokens(tree.root_node(), start, end, &mut raw);
+            raw
+        });
+        for (sp, ep, kind) in raw {
+            if ep.row < start || sp.row > end {
+                continue;
+            }
+            tokens.push(Token {
+                start_offset: Offset {
+                    row: sp.row,
+                    col: sp.column,
+                },
+                end_offset: Offset {
+                    row: ep.row,
+                    col: ep.column,
+                },
+                kind,
+            });
+        }
+    }
+
+    if tokens.is_empty() {
+        for row in start..=end.min(lines.len().saturating_sub(1)) {
+            let len = lines.get(row).map(|l| l.len()).unwrap_or(0);
+            tokens.push(Token {
+                start_offset: Offset { row, col: 0 },
+                end_offset: Offset { row, col: len },
+                kind: "untokenized".to_string(),
+            });
+        }
+    }
+
+    tokens
+}
+
 fn collect_ts_tokens(
     node: tree_sitter::Node,
     row_start: usize,
@@ -683,46 +1990,93 @@ fn create_empty_file(
     state: State<'_, EditorState>,
     path: String,
 ) -> Result<FileMetadata, String> {
-    let pb = PathBuf::from(&path);
+    let pb = resolve_in_workspace(&app, &path)?;
+    reject_traversal(&pb)?;
     if let Some(parent) = pb.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
     fs::write(&pb, "").map_err(|e| e.to_string())?;
 
-    let name = pb
-        .file_name()
-        .and_then(|s| s.to_str())
-        .unwrap_or("")
-        .to_string();
-    let language = detect_language_from_extension(&pb);
-    let lines: Vec<String> = vec![String::new()];
-
-    let meta = FileMetadata {
-        name: name.clone(),
-        path: path.clone(),
-        size: 0,
-        language: language.clone(),
-        line_count: lines.len(),
-    };
-
-    {
-        let mut guard = state.0.lock().unwrap();
-        *guard = Some(FileState {
-            path: pb,
-            name,
-            size: 0,
-            language,
-            lines,
-        });
-    }
-
+    let mut file = FileState::from_text(pb.clone(), "", detect_language_from_extension(&pb), false);
+    file.disk = diskwatch::DiskWatch::start(&app, &pb);
+    let meta = buffers::metadata(&app, &file);
     app.emit("file-opened", &meta)
         .map_err(|e| e.to_string())
         .ok();
+    emit_buffer_state(&app, &mut file);
+    state.0.lock().unwrap().open(file);
 
     Ok(meta)
 }
 
+#[tauri::command]
+fn write_text_to_file(app: AppHandle, path: String, content: String) -> Result<(), String> {
+    let pb = resolve_in_workspace(&app, &path)?;
+    reject_traversal(&pb)?;
+    if let Some(parent) = pb.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    write_atomic(&pb, content.as_bytes())
+}
+
+// Appends `text` after the buffer's last character, keeping the breaks it was written with.
+fn append_rows(file: &mut FileState, text: &str) -> Result<history::LineSplice, String> {
+    let last = file.lines.len() - 1;
+    let end = Offset {
+        row: last,
+        col: file.lines[last].len(),
+    };
+    let (splice, _) = edit::replace_range(file, &end, &end, &split_lines(text).join("\n"))?;
+    let appended = endings::breaks_of(text);
+    let kept = file.breaks.len() - appended.len();
+    file.breaks[kept..].copy_from_slice(&appended);
+    Ok(splice)
+}
+
+// Appending the text at the end of the buffer keeps it in step with the file whether or
+// not the file ended with a newline, breaks included. A buffer with
+// unsaved edits no longer mirrors the file, so it is left alone; writing outside
+// `guard_write` lets the disk watcher report the change as it would any other.
+#[tauri::command]
+fn append_to_file(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    path: String,
+    text: String,
+) -> Result<(), String> {
+    let pb = resolve_in_workspace(&app, &path)?;
+    reject_traversal(&pb)?;
+    let append = || {
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&pb)
+            .and_then(|mut out| out.write_all(text.as_bytes()))
+            .map_err(|e| e.to_string())
+    };
+
+    let mut guard = state.0.lock().unwrap();
+    let file = match guard.get_mut(&pb) {
+        Some(f) if !f.dirty => f,
+        _ => return append(),
+    };
+    file.disk.guard_write(&pb, append)?;
+
+    let old_total = file.lines.len();
+    let splice = append_rows(file, &text)?;
+    file.ending_counts = endings::EndingCounts::tally(&file.breaks);
+    file.disk_size = fs::metadata(&pb)
+        .map(|m| m.len())
+        .unwrap_or(file.disk_size + text.len() as u64);
+    // The file on disk already holds the appended text.
+    file.dirty = false;
+
+    edit::emit_changed(&app, file, splice.start, file.lines.len(), old_total);
+    file.history.push(vec![splice]);
+    history::emit_availability(&app, &mut file.history);
+    Ok(())
+}
+
 async fn process_queued_file(app: &AppHandle, path: &String) {
     println!("Opening file: {}", &path);
     app.emit("queue-file-open", path)
@@ -732,12 +2086,24 @@ async fn process_queued_file(app: &AppHandle, path: &String) {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 #[tauri::command]
-fn copy_path(src: String, dest: String) -> Result<(), String> {
-    let src_pb = PathBuf::from(&src);
+fn copy_path(
+    app: AppHandle,
+    src: String,
+    dest: String,
+    dry_run: Option<bool>,
+    check_space: Option<bool>,
+) -> Result<Option<OperationPlan>, String> {
+    let src_pb = resolve_path(&app, &src)?;
     if !src_pb.exists() {
         return Err("source does not exist".into());
     }
-    let dest_pb = PathBuf::from(&dest);
+    if dry_run.unwrap_or(false) {
+        return Ok(Some(plan_operation(&src_pb)));
+    }
+    let dest_pb = resolve_in_workspace(&app, &dest)?;
+    if check_space.unwrap_or(false) {
+        disk::ensure_space(&dest_pb, disk::path_size(&src_pb))?;
+    }
 
     if src_pb.is_dir() {
         copy_dir_recursive(&src_pb, &dest_pb).map_err(|e| e.to_string())?;
@@ -747,7 +2113,34 @@ fn copy_path(src: String, dest: String) -> Result<(), String> {
         }
         fs::copy(&src_pb, &dest_pb).map_err(|e| e.to_string())?;
     }
-    Ok(())
+    Ok(None)
+}
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OperationPlan {
+    paths: Vec<String>,
+    total_bytes: u64,
+}
+
+fn collect_plan(path: &Path, plan: &mut OperationPlan) {
+    plan.paths.push(path.to_string_lossy().to_string());
+    if path.is_dir() {
+        if let Ok(rd) = fs::read_dir(path) {
+            for entry in rd.flatten() {
+                collect_plan(&entry.path(), plan);
+            }
+        }
+    } else {
+        plan.total_bytes += fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    }
+}
+
+// Lists every path a copy, move or delete of `root` would touch, without modifying anything.
+fn plan_operation(root: &Path) -> OperationPlan {
+    let mut plan = OperationPlan::default();
+    collect_plan(root, &mut plan);
+    plan
 }
 
 fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), std::io::Error> {
@@ -773,64 +2166,290 @@ fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), std::io::Error> {
     Ok(())
 }
 
-#[tauri::command]
-fn move_path(src: String, dest: String) -> Result<(), String> {
-    let src_pb = PathBuf::from(&src);
-    if !src_pb.exists() {
-        return Err("source does not exist".into());
+// Rekeys every buffer under `src` to its place under `dest`, carrying its name, language and
+// backup files along. Returns (old path, new path, language changed) for each one moved.
+fn retarget_buffers(
+    buffers: &mut buffers::Buffers,
+    src: &Path,
+    dest: &Path,
+) -> Vec<(PathBuf, PathBuf, bool)> {
+    let moved: Vec<(PathBuf, PathBuf)> = buffers
+        .paths()
+        .iter()
+        .filter_map(|old| {
+            let new_path = match old.strip_prefix(src) {
+                Ok(rest) if rest.as_os_str().is_empty() => dest.to_path_buf(),
+                Ok(rest) => dest.join(rest),
+                Err(_) => return None,
+            };
+            Some((old.clone(), new_path))
+        })
+        .collect();
+
+    let mut retargeted = Vec::new();
+    for (old_path, new_path) in moved {
+        buffers.rekey(&old_path, new_path.clone());
+        let file = match buffers.get_mut(&new_path) {
+            Some(f) => f,
+            None => continue,
+        };
+        backups::migrate(&old_path, &new_path);
+        file.name = new_path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let old_language = std::mem::replace(
+            &mut file.language,
+            detect_language_from_extension(&new_path),
+        );
+        let language_changed = old_language != file.language;
+        if language_changed {
+            file.parse_cache.get_mut().take();
+        }
+        retargeted.push((old_path, new_path, language_changed));
     }
+    retargeted
+}
+
+// Follows open buffers when they (or a directory containing them) are moved on disk.
+fn retarget_open_buffer(app: &AppHandle, state: &EditorState, src: &Path, dest: &Path) {
+    let mut guard = state.0.lock().unwrap();
+    let active_moved = guard.active().is_some_and(|f| f.path.starts_with(src));
+
+    for (old_path, new_path, language_changed) in retarget_buffers(&mut guard, src, dest) {
+        let file = match guard.get_mut(&new_path) {
+            Some(f) => f,
+            None => continue,
+        };
+        file.disk = diskwatch::DiskWatch::start(app, &new_path);
+
+        app.emit(
+            "buffer-path-changed",
+            serde_json::json!({
+              "oldPath": old_path.to_string_lossy(),
+              "path": new_path.to_string_lossy(),
+              "name": file.name,
+              "language": file.language,
+              "languageChanged": language_changed
+            }),
+        )
+        .map_err(|e| e.to_string())
+        .ok();
+    }
+
+    // The active tab reloads its title and language the same way it does on open.
+    if let Some(file) = guard.active().filter(|_| active_moved) {
+        app.emit("file-opened", buffers::metadata(app, file))
+            .map_err(|e| e.to_string())
+            .ok();
+    }
+}
 
-    let dest_pb = PathBuf::from(&dest);
+// Shared by `move_path` and `rename_path` once both ends are resolved and checked.
+fn move_resolved(
+    app: &AppHandle,
+    state: &EditorState,
+    src_pb: &Path,
+    dest_pb: &Path,
+    overwrite: bool,
+) -> Result<(), String> {
+    let check_dest = || {
+        if !dest_pb.exists() {
+            return Ok(());
+        }
+        if !overwrite {
+            return Err("destination already exists".to_string());
+        }
+        let guard = state.0.lock().unwrap();
+        if guard.iter().any(|f| f.dirty && f.path.starts_with(dest_pb)) {
+            return Err("destination has unsaved changes open in the editor".to_string());
+        }
+        Ok(())
+    };
+    check_dest()?;
     if let Some(parent) = dest_pb.parent() {
         if !parent.exists() {
             fs::create_dir_all(parent).map_err(|e| e.to_string())?;
         }
     }
 
-    match fs::rename(&src_pb, &dest_pb) {
-        Ok(_) => Ok(()),
+    match fs::rename(src_pb, dest_pb) {
+        Ok(_) => {}
         Err(e) => {
             if e.kind() == std::io::ErrorKind::CrossesDevices {
+                check_dest()?;
                 if src_pb.is_dir() {
-                    copy_dir_recursive(&src_pb, &dest_pb).map_err(|e| e.to_string())?;
+                    copy_dir_recursive(src_pb, dest_pb).map_err(|e| e.to_string())?;
 
-                    fs::remove_dir_all(&src_pb).map_err(|e| e.to_string())?;
+                    fs::remove_dir_all(src_pb).map_err(|e| e.to_string())?;
                 } else {
                     if let Some(parent) = dest_pb.parent() {
                         if !parent.exists() {
                             fs::create_dir_all(parent).map_err(|e| e.to_string())?;
                         }
                     }
-                    fs::copy(&src_pb, &dest_pb).map_err(|e| e.to_string())?;
-                    fs::remove_file(&src_pb).map_err(|e| e.to_string())?;
+                    fs::copy(src_pb, dest_pb).map_err(|e| e.to_string())?;
+                    fs::remove_file(src_pb).map_err(|e| e.to_string())?;
                 }
-                Ok(())
             } else {
-                Err(e.to_string())
+                return Err(e.to_string());
             }
         }
     }
+    app.emit(
+        "path-moved",
+        PathMovedPayload {
+            src: src_pb.to_string_lossy().to_string(),
+            dest: dest_pb.to_string_lossy().to_string(),
+        },
+    )
+    .map_err(|e| e.to_string())
+    .ok();
+    retarget_open_buffer(app, state, src_pb, dest_pb);
+    Ok(())
+}
+
+#[tauri::command]
+fn move_path(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    src: String,
+    dest: String,
+    dry_run: Option<bool>,
+    overwrite: Option<bool>,
+) -> Result<Option<OperationPlan>, String> {
+    let src_pb = resolve_in_workspace(&app, &src)?;
+    if !src_pb.exists() {
+        return Err("source does not exist".into());
+    }
+    if dry_run.unwrap_or(false) {
+        return Ok(Some(plan_operation(&src_pb)));
+    }
+
+    let dest_pb = resolve_in_workspace(&app, &dest)?;
+    move_resolved(&app, &state, &src_pb, &dest_pb, overwrite.unwrap_or(false))?;
+    Ok(None)
+}
+
+// Keeps the parent directory and swaps only the final component.
+#[tauri::command]
+fn rename_path(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    path: String,
+    new_name: String,
+) -> Result<String, String> {
+    if new_name.is_empty()
+        || new_name == "."
+        || new_name == ".."
+        || new_name.chars().any(std::path::is_separator)
+    {
+        return Err("new name must be a single path component".into());
+    }
+    let src_pb = resolve_in_workspace(&app, &path)?;
+    if !src_pb.exists() {
+        return Err("source does not exist".into());
+    }
+    let dest_pb = src_pb
+        .parent()
+        .ok_or("cannot rename the filesystem root")?
+        .join(&new_name);
+    move_resolved(&app, &state, &src_pb, &dest_pb, false)?;
+    Ok(dest_pb.to_string_lossy().to_string())
 }
 
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeleteOutcome {
+    plan: Option<OperationPlan>,
+    warning: Option<String>,
+}
+
+fn remove_permanently(pb: &Path) -> Result<(), String> {
+    if pb.is_dir() {
+        fs::remove_dir_all(pb).map_err(|e| e.to_string())
+    } else {
+        fs::remove_file(pb).map_err(|e| e.to_string())
+    }
+}
+
+// Returns a warning when the path had to be deleted permanently instead.
+#[cfg(not(any(target_os = "android", target_os = "ios")))]
+fn move_to_trash(pb: &Path) -> Result<Option<String>, String> {
+    trash::delete(pb)
+        .map_err(|e| format!("could not move to trash: {} (pass permanent to delete)", e))?;
+    Ok(None)
+}
+
+#[cfg(any(target_os = "android", target_os = "ios"))]
+fn move_to_trash(pb: &Path) -> Result<Option<String>, String> {
+    remove_permanently(pb)?;
+    Ok(Some(
+        "trash is not available on this platform; deleted permanently".into(),
+    ))
+}
+
+// Paths go to the system trash unless `permanent` is set. Only platforms without a trash
+// fall back to deleting outright; a failed move is an error so nothing is lost silently.
 #[tauri::command]
-fn delete_path(path: String) -> Result<(), String> {
-    let pb = PathBuf::from(&path);
+fn delete_path(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    path: String,
+    dry_run: Option<bool>,
+    permanent: Option<bool>,
+) -> Result<DeleteOutcome, String> {
+    let pb = resolve_in_workspace(&app, &path)?;
     if !pb.exists() {
         return Err("path does not exist".into());
     }
-    if pb.is_dir() {
-        fs::remove_dir_all(&pb).map_err(|e| e.to_string())?;
+    if dry_run.unwrap_or(false) {
+        return Ok(DeleteOutcome {
+            plan: Some(plan_operation(&pb)),
+            warning: None,
+        });
+    }
+    let outcome = if permanent.unwrap_or(false) {
+        remove_permanently(&pb)?;
+        DeleteOutcome::default()
     } else {
-        fs::remove_file(&pb).map_err(|e| e.to_string())?;
+        DeleteOutcome {
+            plan: None,
+            warning: move_to_trash(&pb)?,
+        }
+    };
+    orphan_buffers(&app, &state, &pb);
+    Ok(outcome)
+}
+
+// Buffers whose file was just deleted stay open with their contents, but are marked dirty
+// so saving one is a deliberate choice to recreate the file rather than a quiet side effect.
+fn orphan_buffers(app: &AppHandle, state: &EditorState, root: &Path) {
+    let mut guard = state.0.lock().unwrap();
+    let orphaned: Vec<PathBuf> = guard
+        .paths()
+        .iter()
+        .filter(|p| p.starts_with(root))
+        .cloned()
+        .collect();
+    for path in orphaned {
+        if let Some(file) = guard.get_mut(&path) {
+            file.dirty = true;
+            emit_dirty(app, file);
+            app.emit(
+                "buffer-orphaned",
+                serde_json::json!({ "path": path.to_string_lossy() }),
+            )
+            .map_err(|e| e.to_string())
+            .ok();
+        }
     }
-    Ok(())
 }
 
 #[tauri::command]
 fn open_settings() {
-    let local_app_data = std::env::var("LOCALAPPDATA").unwrap_or_else(|_| ".".to_string());
-    let load_dir = PathBuf::from(local_app_data).join("load");
-    let settings_path = load_dir.join("settings.json");
+    let settings_path = settings::settings_path();
 
     if settings_path.exists() {
         get_file_queue_pool().add_task(settings_path.to_string_lossy().to_string());
@@ -859,11 +2478,7 @@ pub fn run() {
 
         if path.exists() {
             let is_directory = path.is_dir();
-            let canonical_path = path.canonicalize().unwrap_or(path.clone());
-            let mut path_str = canonical_path.to_string_lossy().to_string();
-            if cfg!(windows) && path_str.starts_with(r"\\?\") {
-                path_str = path_str[4..].to_string();
-            }
+            let path_str = canonical_path(&path).to_string_lossy().to_string();
 
             println!(
                 "Storing initial path: {} (is_directory: {})",
@@ -880,23 +2495,42 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .manage(EditorState::default())
         .manage(initial_path_state)
+        .manage(WorkingDirState::default())
+        .manage(WorkspaceState::default())
+        .manage(ClosedBuffers::default())
+        .manage(search::SearchRegistry::default())
+        .manage(tail::TailRegistry::default())
+        .manage(ai::OllamaProcesses::default())
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             get_settings,
             get_initial_path,
+            set_working_directory,
+            get_working_directory,
             ready,
             open_file,
             create_empty_file,
+            write_text_to_file,
+            append_to_file,
             read_directory_root,
             read_directory_children,
+            refresh_directory,
+            list_dir_page,
+            path_ancestors_within_root,
+            is_within_workspace,
             read_line,
+            line_length_info,
             write_line,
             insert_line,
             remove_line,
             request_tokenization,
+            tokenize_path,
             save_buffer,
+            save_buffer_incremental,
+            confirm_lossy_save,
             change_language,
             close_file,
+            reopen_last_closed,
             copy_path,
             move_path,
             delete_path,
@@ -904,8 +2538,285 @@ pub fn run() {
             ai::ollama_available,
             ai::ollama_model_is_downloaded,
             ai::ollama_pull_model,
-            ai::ollama_generate
+            ai::ollama_generate,
+            anchors::create_anchor,
+            anchors::resolve_anchor,
+            ai::ai_buffer_context,
+            backups::purge_backups,
+            bench::benchmark_tokenization,
+            bookmarks::toggle_bookmark,
+            bookmarks::list_bookmarks,
+            bookmarks::clear_bookmarks,
+            diff::diff_files,
+            diff::diff_with_text,
+            diff::only_whitespace_changed,
+            diff::pending_changes_summary,
+            disk::disk_free_space,
+            edit::insert_text,
+            edit::apply_transaction,
+            filetype::file_type_info,
+            format::format_buffer,
+            git::git_blame,
+            history::undo,
+            history::redo,
+            history::undo_history_info,
+            history::clear_undo_history,
+            endings::normalize_line_endings,
+            indent::set_tab_width,
+            indent::set_indent_style,
+            indent::get_indent_config,
+            indent::suggest_indent,
+            indent::indent_range,
+            indent::retab,
+            indent::toggle_comment,
+            locate::open_file_at,
+            locate::open_path_from_text,
+            project::detect_project_language,
+            related::related_files,
+            search::search_in_directory,
+            search::search_cancel,
+            syntax::next_sibling_node,
+            syntax::next_node_of_kind,
+            syntax::enclosing_scope,
+            syntax::get_parse_errors,
+            syntax::language_node_kinds,
+            syntax::parse_tree_json,
+            syntax::styled_lines,
+            syntax::export_html,
+            syntax::structure_outline,
+            themes::list_themes,
+            themes::load_theme,
+            themes::save_theme,
+            todos::list_todos,
+            todos::list_todos_in_directory,
+            identity::buffer_identity,
+            related::toggle_source_output,
+            partial::read_byte_range,
+            syntax::document_structure,
+            edit::split_buffer_at,
+            langguess::guess_language_from_content,
+            tail::tail_file,
+            tail::untail_file,
+            dirhash::hash_directory,
+            dirhash::changed_since,
+            snippet::expand_snippet,
+            syntax::statement_range_at,
+            replace::replace_in_range,
+            indent::reindent_pasted,
+            project::recently_modified_files,
+            syntax::buffer_has_syntax_errors,
+            symbols::build_workspace_symbols,
+            symbols::search_workspace_symbols,
+            diff::diff_directories,
+            settings::effective_settings,
+            settings::update_workspace_settings,
+            ai::ollama_generate_stream,
+            ai::ollama_cancel,
+            edit::apply_edit,
+            is_dirty,
+            diskwatch::check_external_modification,
+            replace::replace_in_directory,
+            endings::set_line_ending,
+            buffers::switch_buffer,
+            buffers::list_open_buffers,
+            supported_languages,
+            syntax::request_diagnostics,
+            syntax::request_fold_ranges,
+            tags::document_symbols,
+            rename_path,
+            buffers::reload_buffer
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer(contents: &str) -> FileState {
+        FileState::from_text(
+            PathBuf::from("test.rs"),
+            contents,
+            "rust".to_string(),
+            false,
+        )
+    }
+
+    fn at(row: usize, col: usize) -> Offset {
+        Offset { row, col }
+    }
+
+    // Removed on drop, so a failing assertion doesn't leave the directory behind.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("load-{}-{}", name, std::process::id()));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        fn join(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn empty_buffer_has_one_line_and_accepts_edits() {
+        let mut file = buffer("");
+        assert_eq!(file.lines, vec![String::new()]);
+
+        edit::replace_range(&mut file, &at(0, 0), &at(0, 0), "fn main() {}\n").unwrap();
+        assert_eq!(file.lines, vec!["fn main() {}".to_string(), String::new()]);
+
+        edit::replace_range(&mut file, &at(0, 0), &at(1, 0), "").unwrap();
+        assert_eq!(file.lines, vec![String::new()]);
+        assert_eq!(file.size, 0);
+    }
+
+    #[test]
+    fn removing_the_last_line_leaves_an_empty_one() {
+        let mut file = buffer("only");
+        let splice = remove_row(&mut file, 0).unwrap();
+        assert_eq!(file.lines, vec![String::new()]);
+        assert_eq!(splice.old, vec!["only".to_string()]);
+        assert!(remove_row(&mut file, 1).is_none());
+
+        let mut file = buffer("a\nb");
+        remove_row(&mut file, 1).unwrap();
+        assert_eq!(file.lines, vec!["a".to_string()]);
+    }
+
+    #[test]
+    fn tokenizing_an_empty_buffer_yields_one_empty_row() {
+        let file = buffer("");
+        let (start, end) = clamp_line_range(file.lines.len(), 0, 10);
+        let tree = parse_text(&file.language, "");
+        let tokens = tokenize_range(&file.language, tree.as_ref(), &file.lines, start, end);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(
+            (tokens[0].start_offset.row, tokens[0].end_offset.col),
+            (0, 0)
+        );
+    }
+
+    #[test]
+    fn reversed_and_equal_ranges_are_ordered_and_clamped() {
+        assert_eq!(clamp_line_range(10, 7, 3), (3, 7));
+        assert_eq!(clamp_line_range(10, 4, 4), (4, 4));
+        assert_eq!(clamp_line_range(10, 25, 8), (8, 9));
+        assert_eq!(clamp_line_range(0, 3, 1), (0, 0));
+    }
+
+    #[test]
+    fn tokenizing_a_reversed_range_matches_the_ordered_one() {
+        let source = "fn a() {}\nfn b() {}\nfn c() {}";
+        let file = buffer(source);
+        let tree = parse_text(&file.language, source);
+        let rows = |start, end| {
+            let (start, end) = clamp_line_range(file.lines.len(), start, end);
+            tokenize_range(&file.language, tree.as_ref(), &file.lines, start, end)
+                .iter()
+                .map(|t| (t.start_offset.row, t.start_offset.col, t.end_offset.col))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(rows(2, 0), rows(0, 2));
+        let single = rows(1, 1);
+        assert!(!single.is_empty());
+        assert!(single.iter().all(|(row, _, _)| *row == 1));
+    }
+
+    #[test]
+    fn incremental_saves_only_append_in_place() {
+        let dir = TempDir::new("partial");
+        let path = dir.join("big.txt");
+        let text: String = (0..100).map(|i| format!("line {:03}\n", i)).collect();
+        fs::write(&path, &text).unwrap();
+        let mut file = FileState::from_text(path.clone(), &text, "text".to_string(), false);
+
+        assert!(matches!(
+            write_incremental(&mut file).unwrap(),
+            SaveOutcome::NoChanges
+        ));
+        edit::replace_range(&mut file, &at(100, 0), &at(100, 0), "line 100\n").unwrap();
+        assert!(matches!(
+            write_incremental(&mut file).unwrap(),
+            SaveOutcome::Appended
+        ));
+        edit::replace_range(&mut file, &at(10, 5), &at(10, 8), "ten").unwrap();
+        assert!(matches!(
+            write_incremental(&mut file).unwrap(),
+            SaveOutcome::Written
+        ));
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), file.lines.join("\n"));
+    }
+
+    #[test]
+    fn appended_text_keeps_its_own_line_breaks() {
+        let mut file = buffer("a\r\nb");
+        let splice = append_rows(&mut file, "c\r\nd\n").unwrap();
+        assert_eq!(splice.start, 1);
+        assert_eq!(file.lines, vec!["a", "bc", "d", ""]);
+        assert_eq!(endings::saved_text(&file).0, "a\r\nbc\r\nd\n");
+    }
+
+    #[test]
+    fn saving_after_renaming_the_open_file_writes_the_new_path() {
+        let dir = TempDir::new("rename");
+        let (old, new) = (dir.join("notes.txt"), dir.join("notes.rs"));
+        fs::write(&old, "before").unwrap();
+
+        let mut buffers = buffers::Buffers::default();
+        buffers.open(FileState::from_text(
+            old.clone(),
+            "before",
+            "text".to_string(),
+            false,
+        ));
+        fs::rename(&old, &new).unwrap();
+        let moved = retarget_buffers(&mut buffers, &old, &new);
+        assert_eq!(moved, vec![(old.clone(), new.clone(), true)]);
+
+        let file = buffers.active_mut().unwrap();
+        assert_eq!(
+            (file.path.as_path(), file.name.as_str()),
+            (new.as_path(), "notes.rs")
+        );
+        edit::replace_range(file, &at(0, 0), &at(0, 6), "after").unwrap();
+        save_file_state(file).unwrap();
+
+        assert_eq!(fs::read_to_string(&new).unwrap(), "after");
+        assert!(!old.exists());
+    }
+
+    #[test]
+    fn mixed_line_endings_survive_an_edit_and_save() {
+        let dir = TempDir::new("endings");
+        let path = dir.join("mixed.txt");
+        let original = "one\r\ntwo\nthree\r\nfour";
+        fs::write(&path, original).unwrap();
+
+        let mut file = FileState::from_text(path.clone(), original, "text".to_string(), false);
+        assert!(file.ending_counts.is_mixed());
+        save_file_state(&mut file).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+
+        edit::replace_range(&mut file, &at(1, 0), &at(1, 3), "TWO").unwrap();
+        edit::replace_range(&mut file, &at(3, 4), &at(3, 4), "\nfive").unwrap();
+        // The endings come from the buffer, not from whatever is on disk by now.
+        fs::write(&path, "rewritten\nelsewhere\n").unwrap();
+        save_file_state(&mut file).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "one\r\nTWO\nthree\r\nfour\r\nfive"
+        );
+    }
+}
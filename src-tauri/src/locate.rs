@@ -0,0 +1,110 @@
+use std::path::PathBuf;
+
+use tauri::{AppHandle, Emitter, State};
+
+use crate::{
+    expand_path, open_resolved, resolve_path, resolve_typed_path, EditorState, FileMetadata, Offset,
+};
+
+const TRIM_CHARS: &[char] = &['"', '\'', '`', '<', '>', '[', ']', ',', ';'];
+
+// Splits `path:line:col`, `path:line` or `path(line,col)` into the path and a 1-based position.
+fn split_location(candidate: &str) -> (&str, Option<usize>, Option<usize>) {
+    if let Some(inner) = candidate.strip_suffix(')') {
+        if let Some((path, pos)) = inner.rsplit_once('(') {
+            let mut nums = pos.split(',').map(|n| n.trim().parse::<usize>());
+            if let Some(Ok(line)) = nums.next() {
+                let col = nums.next().and_then(|n| n.ok());
+                return (path, Some(line), col);
+            }
+        }
+    }
+
+    let trimmed = candidate.trim_end_matches(':');
+    let mut numbers = Vec::new();
+    let mut path = trimmed;
+    while numbers.len() < 2 {
+        match path.rsplit_once(':') {
+            Some((head, tail)) if !tail.is_empty() && tail.chars().all(|c| c.is_ascii_digit()) => {
+                numbers.push(tail.parse::<usize>().ok());
+                path = head;
+            }
+            _ => break,
+        }
+    }
+    numbers.reverse();
+    match numbers.as_slice() {
+        [line] => (path, *line, None),
+        [line, col] => (path, *line, *col),
+        _ => (trimmed, None, None),
+    }
+}
+
+fn resolve_candidate(app: &AppHandle, path: &str, base_dir: Option<&str>) -> Option<PathBuf> {
+    let pb = match base_dir {
+        Some(base) => {
+            let base = resolve_path(app, base).ok()?;
+            let literal = base.join(path);
+            if literal.is_file() {
+                literal
+            } else {
+                base.join(expand_path(path).ok()?)
+            }
+        }
+        None => resolve_typed_path(app, path).ok()?,
+    };
+    pb.is_file().then_some(pb)
+}
+
+fn reveal(app: &AppHandle, line: Option<usize>, col: Option<usize>) {
+    if let Some(line) = line {
+        let offset = Offset {
+            row: line.saturating_sub(1),
+            col: col.unwrap_or(1).saturating_sub(1),
+        };
+        app.emit("reveal-position", &offset)
+            .map_err(|e| e.to_string())
+            .ok();
+    }
+}
+
+#[tauri::command]
+pub fn open_file_at(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    path: String,
+    line: Option<usize>,
+    col: Option<usize>,
+) -> Result<FileMetadata, String> {
+    let meta = open_resolved(&app, &state, resolve_typed_path(&app, &path)?)?;
+    reveal(&app, line, col);
+    Ok(meta)
+}
+
+// Tries the whole selection first, then each whitespace-separated word, so paths inside
+// compiler messages like "error at src/main.rs:10:5" are still found.
+#[tauri::command]
+pub fn open_path_from_text(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    text: String,
+    base_dir: Option<String>,
+) -> Result<FileMetadata, String> {
+    let whole = text.trim();
+    let candidates = std::iter::once(whole).chain(whole.split_whitespace());
+
+    for candidate in candidates {
+        let candidate = candidate.trim_matches(TRIM_CHARS);
+        if candidate.is_empty() {
+            continue;
+        }
+        let (path, line, col) = split_location(candidate);
+        if let Some(pb) = resolve_candidate(&app, path, base_dir.as_deref()) {
+            let meta = open_resolved(&app, &state, pb)?;
+            reveal(&app, line, col);
+            return Ok(meta);
+        }
+    }
+
+    Err(format!("no existing file path found in \"{}\"", whole))
+}
@@ -0,0 +1,107 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{resolve_path, settings};
+
+const MAX_RANGE_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ByteRange {
+    text: String,
+    /// Byte offsets actually covered after trimming partial characters; `end` is exclusive
+    /// and is where the next page should start.
+    start: u64,
+    end: u64,
+    file_size: u64,
+    eof: bool,
+    lossy: bool,
+}
+
+fn is_continuation(b: u8) -> bool {
+    b & 0xC0 == 0x80
+}
+
+fn sequence_len(lead: u8) -> usize {
+    match lead {
+        0xF0..=0xF7 => 4,
+        0xE0..=0xEF => 3,
+        0xC0..=0xDF => 2,
+        _ => 1,
+    }
+}
+
+// Length of `buf` without a multibyte sequence cut off by the end of the read.
+fn complete_len(buf: &[u8]) -> usize {
+    let tail = buf.len().saturating_sub(3);
+    match (tail..buf.len()).rev().find(|&i| !is_continuation(buf[i])) {
+        Some(i) if i + sequence_len(buf[i]) > buf.len() => i,
+        _ => buf.len(),
+    }
+}
+
+#[tauri::command]
+pub fn read_byte_range(
+    app: AppHandle,
+    path: String,
+    offset: u64,
+    length: u64,
+) -> Result<ByteRange, String> {
+    let pb = resolve_path(&app, &path)?;
+    let mut file = File::open(&pb).map_err(|e| e.to_string())?;
+    let file_size = file.metadata().map_err(|e| e.to_string())?.len();
+
+    let start = offset.min(file_size);
+    let mut length = length.min(MAX_RANGE_BYTES).min(file_size - start);
+    let (buf, skip, keep) = loop {
+        let mut buf = vec![0; length as usize];
+        file.seek(SeekFrom::Start(start))
+            .map_err(|e| e.to_string())?;
+        file.read_exact(&mut buf).map_err(|e| e.to_string())?;
+
+        let skip = buf
+            .iter()
+            .take(3)
+            .take_while(|b| is_continuation(**b))
+            .count();
+        let at_eof = start + length == file_size;
+        let keep = if at_eof {
+            buf.len()
+        } else {
+            complete_len(&buf).max(skip)
+        };
+        // A `length` shorter than one character still has to move the reader forward, so
+        // the range grows until it holds a whole one.
+        if keep > skip || at_eof {
+            break (buf, skip, keep);
+        }
+        length = (skip as u64 + 4).min(file_size - start);
+    };
+    let bytes = &buf[skip..keep];
+
+    let (text, lossy) = match std::str::from_utf8(bytes) {
+        Ok(s) => (s.to_string(), false),
+        Err(_) if settings::get_bool("lossy_utf8_fallback", false) => {
+            (String::from_utf8_lossy(bytes).into_owned(), true)
+        }
+        Err(e) => {
+            return Err(format!(
+                "invalid UTF-8 at byte {}",
+                start + skip as u64 + e.valid_up_to() as u64
+            ))
+        }
+    };
+
+    let end = start + keep as u64;
+    Ok(ByteRange {
+        text,
+        start: start + skip as u64,
+        end,
+        file_size,
+        eof: end >= file_size,
+        lossy,
+    })
+}
@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use rayon::prelude::*;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+    detect_language_from_extension, dirhash, get_ts_language, is_binary_content, resolve_path,
+    walk_files,
+};
+
+const MAX_SAMPLED_FILES: usize = 500;
+const DEFAULT_RECENT_LIMIT: usize = 50;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LangStat {
+    language: String,
+    files: usize,
+    lines: usize,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMtime {
+    path: String,
+    /// Milliseconds since the Unix epoch.
+    modified: u64,
+}
+
+// Stops after MAX_SAMPLED_FILES recognised source files, so large trees are only sampled.
+fn sample_languages(root: &PathBuf) -> Vec<LangStat> {
+    let mut stats: HashMap<String, LangStat> = HashMap::new();
+    let mut sampled = 0;
+
+    walk_files(root, |path| {
+        let language = detect_language_from_extension(&path.to_path_buf());
+        if get_ts_language(&language).is_none() {
+            return true;
+        }
+        let bytes = match fs::read(path) {
+            Ok(b) if !is_binary_content(&b) => b,
+            _ => return true,
+        };
+        let lines = bytes.iter().filter(|&&b| b == b'\n').count() + 1;
+
+        let stat = stats.entry(language.clone()).or_insert_with(|| LangStat {
+            language,
+            files: 0,
+            lines: 0,
+        });
+        stat.files += 1;
+        stat.lines += lines;
+        sampled += 1;
+        sampled < MAX_SAMPLED_FILES
+    });
+
+    let mut ranking: Vec<LangStat> = stats.into_values().collect();
+    ranking.sort_by(|a, b| {
+        b.lines
+            .cmp(&a.lines)
+            .then_with(|| a.language.cmp(&b.language))
+    });
+    ranking
+}
+
+#[tauri::command]
+pub async fn detect_project_language(
+    app: AppHandle,
+    root: String,
+) -> Result<Vec<LangStat>, String> {
+    let root_pb = resolve_path(&app, &root)?;
+    if !root_pb.is_dir() {
+        return Err("path is not a directory".into());
+    }
+    tokio::task::spawn_blocking(move || sample_languages(&root_pb))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn recently_modified_files(
+    app: AppHandle,
+    root: String,
+    limit: Option<usize>,
+) -> Result<Vec<FileMtime>, String> {
+    let root_pb = resolve_path(&app, &root)?;
+    if !root_pb.is_dir() {
+        return Err("path is not a directory".into());
+    }
+    let limit = limit.unwrap_or(DEFAULT_RECENT_LIMIT);
+    tokio::task::spawn_blocking(move || {
+        let mut files: Vec<FileMtime> = dirhash::collect_files(&root_pb)
+            .par_iter()
+            .filter_map(|path| {
+                let modified = fs::metadata(path).ok()?.modified().ok()?;
+                Some(FileMtime {
+                    path: path.to_string_lossy().to_string(),
+                    modified: modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64,
+                })
+            })
+            .collect();
+        files.sort_by(|a, b| {
+            b.modified
+                .cmp(&a.modified)
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        files.truncate(limit);
+        files
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
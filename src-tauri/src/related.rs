@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::PathBuf;
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{detect_language_from_extension, resolve_path, settings};
+
+// Tried in order; more specific rules come first so `src/a.ts` maps to `dist/a.js`
+// before the plain extension swap is considered.
+const DEFAULT_OUTPUT_MAPPINGS: &[(&str, &str)] = &[
+    (r"^(.*)/src/(.*)\.tsx?$", "$1/dist/$2.js"),
+    (r"^(.*)/dist/(.*)\.js$", "$1/src/$2.ts"),
+    (r"^(.*)/src/(.*)$", "$1/dist/$2"),
+    (r"^(.*)/dist/(.*)$", "$1/src/$2"),
+    (r"^(.*)\.tsx?$", "$1.js"),
+    (r"^(.*)\.js$", "$1.ts"),
+    (r"^(.*)\.s[ac]ss$", "$1.css"),
+    (r"^(.*)\.css$", "$1.scss"),
+];
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedFile {
+    name: String,
+    path: String,
+    relationship: &'static str,
+}
+
+// Extensions a file of each language is commonly paired with, and what the pair is called.
+fn pairing_rules(language: &str) -> &'static [(&'static str, &'static str)] {
+    match language {
+        "c" | "cpp" => &[
+            ("h", "header"),
+            ("hh", "header"),
+            ("hpp", "header"),
+            ("hxx", "header"),
+            ("c", "source"),
+            ("cc", "source"),
+            ("cpp", "source"),
+            ("cxx", "source"),
+        ],
+        "typescript" | "javascript" => &[
+            ("css", "style"),
+            ("scss", "style"),
+            ("sass", "style"),
+            ("less", "style"),
+            ("ts", "source"),
+            ("tsx", "source"),
+            ("js", "source"),
+            ("jsx", "source"),
+        ],
+        "css" => &[
+            ("ts", "source"),
+            ("tsx", "source"),
+            ("js", "source"),
+            ("jsx", "source"),
+            ("html", "source"),
+        ],
+        "html" => &[("css", "style"), ("js", "source"), ("ts", "source")],
+        "ocaml" => &[("mli", "header"), ("ml", "source")],
+        _ => &[],
+    }
+}
+
+struct NameParts<'a> {
+    stem: &'a str,
+    ext: &'a str,
+    is_test: bool,
+}
+
+// `foo.test.ts`, `foo.spec.js`, `foo_test.go` and `test_foo.py` all reduce to stem `foo`.
+fn split_name(name: &str) -> Option<NameParts<'_>> {
+    let (mut stem, rest) = name.split_once('.')?;
+    if stem.is_empty() {
+        return None;
+    }
+    let mut is_test = rest.starts_with("test.") || rest.starts_with("spec.");
+    if let Some(s) = stem.strip_suffix("_test") {
+        stem = s;
+        is_test = true;
+    } else if let Some(s) = stem.strip_prefix("test_") {
+        stem = s;
+        is_test = true;
+    }
+    let ext = rest.rsplit('.').next().unwrap_or(rest);
+    Some(NameParts { stem, ext, is_test })
+}
+
+#[tauri::command]
+pub fn related_files(app: AppHandle, path: String) -> Result<Vec<RelatedFile>, String> {
+    let pb = resolve_path(&app, &path)?;
+    let dir = pb.parent().ok_or("path has no parent directory")?;
+    let own_name = pb
+        .file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let own = match split_name(&own_name) {
+        Some(p) => p,
+        None => return Ok(Vec::new()),
+    };
+    let rules = pairing_rules(&detect_language_from_extension(&pb));
+
+    let mut related = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name == own_name {
+            continue;
+        }
+        let other = match split_name(&name) {
+            Some(p) if p.stem == own.stem => p,
+            _ => continue,
+        };
+
+        let relationship = if other.is_test && !own.is_test {
+            "test"
+        } else if own.is_test && !other.is_test {
+            "source"
+        } else {
+            rules
+                .iter()
+                .find(|(ext, _)| *ext == other.ext)
+                .map(|(_, rel)| *rel)
+                .unwrap_or("related")
+        };
+        related.push(RelatedFile {
+            path: entry.path().to_string_lossy().to_string(),
+            name,
+            relationship,
+        });
+    }
+
+    related.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(related)
+}
+
+// `output_mappings` is a list of `{ "from": regex, "to": replacement }` applied to the
+// path with `/` separators; `$1`-style groups in `to` refer to captures in `from`.
+fn output_mappings() -> Result<Vec<(Regex, String)>, String> {
+    let configured = settings::get("output_mappings").and_then(|v| v.as_array().cloned());
+    let pairs: Vec<(String, String)> = match configured {
+        Some(rules) => rules
+            .iter()
+            .map(|r| {
+                let from = r.get("from").and_then(|v| v.as_str());
+                let to = r.get("to").and_then(|v| v.as_str());
+                match (from, to) {
+                    (Some(f), Some(t)) => Ok((f.to_string(), t.to_string())),
+                    _ => Err("output mapping needs string `from` and `to` fields".to_string()),
+                }
+            })
+            .collect::<Result<_, _>>()?,
+        None => DEFAULT_OUTPUT_MAPPINGS
+            .iter()
+            .map(|(f, t)| (f.to_string(), t.to_string()))
+            .collect(),
+    };
+    pairs
+        .into_iter()
+        .map(|(from, to)| {
+            Regex::new(&from)
+                .map(|re| (re, to))
+                .map_err(|e| format!("invalid output mapping `{}`: {}", from, e))
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn toggle_source_output(app: AppHandle, path: String) -> Result<Option<String>, String> {
+    let pb = resolve_path(&app, &path)?;
+    let normalized = pb.to_string_lossy().replace('\\', "/");
+    for (re, to) in output_mappings()? {
+        if !re.is_match(&normalized) {
+            continue;
+        }
+        let target = PathBuf::from(re.replace(&normalized, to.as_str()).into_owned());
+        if target != pb && target.is_file() {
+            return Ok(Some(target.to_string_lossy().to_string()));
+        }
+    }
+    Ok(None)
+}
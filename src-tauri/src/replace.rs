@@ -0,0 +1,229 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use rayon::prelude::*;
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::{
+    dirhash, edit, emit_dirty, history, read_text_file, resolve_in_workspace, save_file_state,
+    write_atomic, EditorState, FileState, Offset,
+};
+
+// Keeps a pathological pattern from compiling into a huge automaton on the UI's behalf.
+const MAX_PATTERN_BYTES: usize = 1 << 20;
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceOptions {
+    /// Treat `find` as a regular expression and allow `$1`-style groups in the replacement.
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+}
+
+pub fn compile_pattern(find: &str, opts: &ReplaceOptions) -> Result<Regex, String> {
+    if find.is_empty() {
+        return Err("search text is empty".into());
+    }
+    let pattern = if opts.regex {
+        find.to_string()
+    } else {
+        regex::escape(find)
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!opts.case_sensitive)
+        .multi_line(true)
+        .size_limit(MAX_PATTERN_BYTES)
+        .build()
+        .map_err(|e| format!("invalid pattern: {}", e))
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileReplacement {
+    path: String,
+    replacements_made: usize,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileFailure {
+    path: String,
+    error: String,
+}
+
+/// One file failing doesn't stop the others, so both outcomes are reported.
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryReplaceReport {
+    replaced: Vec<FileReplacement>,
+    failed: Vec<FileFailure>,
+}
+
+// Returns the rewritten text and the number of matches, or `None` when nothing matched.
+fn replace_all(re: &Regex, text: &str, replace: &str, regex: bool) -> Option<(String, usize)> {
+    let count = re.find_iter(text).count();
+    if count == 0 {
+        return None;
+    }
+    let replaced = if regex {
+        re.replace_all(text, replace)
+    } else {
+        re.replace_all(text, NoExpand(replace))
+    };
+    Some((replaced.into_owned(), count))
+}
+
+// `app` is `None` for background buffers, whose edits the editor view must not be told about.
+fn replace_rows(
+    app: Option<&AppHandle>,
+    file: &mut FileState,
+    re: &Regex,
+    (start_row, end_row): (usize, usize),
+    replace: &str,
+    regex: bool,
+) -> Result<usize, String> {
+    let text = file.lines[start_row..=end_row].join("\n");
+    let (replaced, count) = match replace_all(re, &text, replace, regex) {
+        Some(v) => v,
+        None => return Ok(0),
+    };
+
+    let start = Offset {
+        row: start_row,
+        col: 0,
+    };
+    let end = Offset {
+        row: end_row,
+        col: file.lines[end_row].len(),
+    };
+    let old_total = file.lines.len();
+    let (splice, _) = edit::replace_range(file, &start, &end, &replaced)?;
+    let end_row = splice.start + splice.new.len().max(splice.old.len());
+    if let Some(app) = app {
+        edit::emit_changed(app, file, splice.start, end_row, old_total);
+    }
+    file.history.push(vec![splice]);
+    if let Some(app) = app {
+        history::emit_availability(app, &mut file.history);
+    }
+    Ok(count)
+}
+
+#[tauri::command]
+pub fn replace_in_range(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    start_row: usize,
+    end_row: usize,
+    find: String,
+    replace: String,
+    opts: Option<ReplaceOptions>,
+) -> Result<usize, String> {
+    let opts = opts.unwrap_or_default();
+    let re = compile_pattern(&find, &opts)?;
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+
+    let last = file.lines.len() - 1;
+    let (start_row, end_row) = (start_row.min(last), end_row.min(last));
+    if start_row > end_row {
+        return Err("range end is before its start".into());
+    }
+    replace_rows(
+        Some(&app),
+        file,
+        &re,
+        (start_row, end_row),
+        &replace,
+        opts.regex,
+    )
+}
+
+// Open files are edited through their buffers rather than on disk, so undo covers them and
+// no buffer goes stale. Each is saved only when it had no unsaved edits of its own.
+#[tauri::command]
+pub async fn replace_in_directory(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    root: String,
+    query: String,
+    replacement: String,
+    opts: Option<ReplaceOptions>,
+    dry_run: Option<bool>,
+) -> Result<DirectoryReplaceReport, String> {
+    let root_pb = resolve_in_workspace(&app, &root)?;
+    if !root_pb.is_dir() {
+        return Err("path is not a directory".into());
+    }
+    let opts = opts.unwrap_or_default();
+    let re = compile_pattern(&query, &opts)?;
+    let dry_run = dry_run.unwrap_or(false);
+    let open_paths: HashSet<PathBuf> = state.0.lock().unwrap().paths().iter().cloned().collect();
+
+    let (pattern, replace) = (re.clone(), replacement.clone());
+    let (mut outcomes, open_matches) = tokio::task::spawn_blocking(move || {
+        let (open, closed): (Vec<PathBuf>, Vec<PathBuf>) = dirhash::collect_files(&root_pb)
+            .into_iter()
+            .partition(|p| open_paths.contains(p));
+        let found = closed
+            .par_iter()
+            .filter_map(|path| {
+                let text = read_text_file(path).ok()?;
+                let (replaced, count) = replace_all(&pattern, &text, &replace, opts.regex)?;
+                let written = if dry_run {
+                    Ok(())
+                } else {
+                    write_atomic(path, replaced.as_bytes())
+                };
+                Some((path.clone(), written.map(|_| count)))
+            })
+            .collect::<Vec<(PathBuf, Result<usize, String>)>>();
+        (found, open)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut guard = state.0.lock().unwrap();
+    let active = guard.active().map(|f| f.path.clone());
+    for path in open_matches {
+        let file = match guard.get_mut(&path) {
+            Some(f) => f,
+            None => continue,
+        };
+        let last = file.lines.len() - 1;
+        let outcome = if dry_run {
+            Ok(re.find_iter(&file.lines.join("\n")).count())
+        } else {
+            let was_dirty = file.dirty;
+            let view = (active.as_ref() == Some(&path)).then_some(&app);
+            replace_rows(view, file, &re, (0, last), &replacement, opts.regex).and_then(|count| {
+                if count > 0 && !was_dirty {
+                    save_file_state(file)?;
+                    emit_dirty(&app, file);
+                }
+                Ok(count)
+            })
+        };
+        outcomes.push((path, outcome));
+    }
+
+    let mut report = DirectoryReplaceReport::default();
+    for (path, outcome) in outcomes {
+        let path = path.to_string_lossy().to_string();
+        match outcome {
+            Ok(0) => {}
+            Ok(count) => report.replaced.push(FileReplacement {
+                path,
+                replacements_made: count,
+            }),
+            Err(error) => report.failed.push(FileFailure { path, error }),
+        }
+    }
+    report.replaced.sort_by(|a, b| a.path.cmp(&b.path));
+    report.failed.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(report)
+}
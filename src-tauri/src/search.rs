@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use regex::Regex;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::replace::{compile_pattern, ReplaceOptions};
+use crate::{read_text_file, resolve_path, walk_files};
+
+#[derive(Default)]
+pub struct SearchRegistry(Mutex<HashMap<String, Arc<AtomicBool>>>);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    search_id: String,
+    path: String,
+    line_number: usize,
+    line_text: String,
+    match_col: usize,
+    /// Byte offset just past the first match on the line.
+    match_end: usize,
+    /// Capture group spans of the first match, in regex mode; `None` for groups that
+    /// didn't participate.
+    groups: Vec<Option<Span>>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct Span {
+    start: usize,
+    end: usize,
+}
+
+struct SearchContext<'a> {
+    app: &'a AppHandle,
+    search_id: &'a str,
+    needle: String,
+    pattern: Option<Regex>,
+    case_sensitive: bool,
+    max_results: usize,
+    cancelled: &'a AtomicBool,
+    hits: Vec<SearchHit>,
+}
+
+// Lowercasing can change a line's byte length, so the match is mapped back to a
+// byte column in the original line.
+fn find_lowercase(line: &str, needle: &str) -> Option<(usize, usize)> {
+    if line.is_ascii() {
+        let col = line.to_ascii_lowercase().find(needle)?;
+        return Some((col, col + needle.len()));
+    }
+    let mut lowered = String::with_capacity(line.len());
+    let mut origin = Vec::with_capacity(line.len());
+    for (i, c) in line.char_indices() {
+        for lc in c.to_lowercase() {
+            lowered.push(lc);
+            origin.resize(lowered.len(), i);
+        }
+    }
+    let col = lowered.find(needle)?;
+    let end = origin
+        .get(col + needle.len())
+        .copied()
+        .unwrap_or(line.len());
+    Some((origin[col], end))
+}
+
+impl SearchContext<'_> {
+    fn should_stop(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || self.hits.len() >= self.max_results
+    }
+
+    fn find(&self, line: &str) -> Option<(usize, usize, Vec<Option<Span>>)> {
+        if let Some(re) = &self.pattern {
+            let caps = re.captures(line)?;
+            let whole = caps.get(0)?;
+            let groups = caps
+                .iter()
+                .skip(1)
+                .map(|g| {
+                    g.map(|m| Span {
+                        start: m.start(),
+                        end: m.end(),
+                    })
+                })
+                .collect();
+            return Some((whole.start(), whole.end(), groups));
+        }
+        let (col, end) = if self.case_sensitive {
+            let col = line.find(&self.needle)?;
+            (col, col + self.needle.len())
+        } else {
+            find_lowercase(line, &self.needle)?
+        };
+        Some((col, end, Vec::new()))
+    }
+
+    fn search_file(&mut self, path: &Path) {
+        let contents = match read_text_file(path) {
+            Ok(v) => v,
+            Err(_) => return,
+        };
+
+        for (idx, line) in contents.lines().enumerate() {
+            if self.should_stop() {
+                return;
+            }
+            if let Some((col, end, groups)) = self.find(line) {
+                let hit = SearchHit {
+                    search_id: self.search_id.to_string(),
+                    path: path.to_string_lossy().to_string(),
+                    line_number: idx,
+                    line_text: line.to_string(),
+                    match_col: col,
+                    match_end: end,
+                    groups,
+                };
+                self.app
+                    .emit("search-result", &hit)
+                    .map_err(|e| eprintln!("Failed to emit search-result event: {}", e))
+                    .ok();
+                self.hits.push(hit);
+            }
+        }
+    }
+}
+
+// Tauri passes each command argument by name, so these stay separate parameters.
+#[allow(clippy::too_many_arguments)]
+#[tauri::command]
+pub async fn search_in_directory(
+    app: AppHandle,
+    registry: State<'_, SearchRegistry>,
+    root: String,
+    query: String,
+    search_id: String,
+    case_sensitive: Option<bool>,
+    max_results: Option<usize>,
+    regex: bool,
+) -> Result<Vec<SearchHit>, String> {
+    let root_pb = resolve_path(&app, &root)?;
+    if !root_pb.is_dir() {
+        return Err("path is not a directory".into());
+    }
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let case_sensitive = case_sensitive.unwrap_or(false);
+    let pattern = if regex {
+        let opts = ReplaceOptions {
+            regex,
+            case_sensitive,
+        };
+        Some(compile_pattern(&query, &opts)?)
+    } else {
+        None
+    };
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    registry
+        .0
+        .lock()
+        .unwrap()
+        .insert(search_id.clone(), cancelled.clone());
+
+    let token = cancelled.clone();
+    let id = search_id.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        let mut ctx = SearchContext {
+            app: &app,
+            search_id: &id,
+            needle: if case_sensitive {
+                query
+            } else {
+                query.to_lowercase()
+            },
+            pattern,
+            case_sensitive,
+            max_results: max_results.unwrap_or(usize::MAX),
+            cancelled: &token,
+            hits: Vec::new(),
+        };
+        walk_files(&root_pb, |path| {
+            ctx.search_file(path);
+            !ctx.should_stop()
+        });
+        ctx.hits
+    })
+    .await
+    .map_err(|e| e.to_string());
+
+    let mut searches = registry.0.lock().unwrap();
+    if searches
+        .get(&search_id)
+        .is_some_and(|t| Arc::ptr_eq(t, &cancelled))
+    {
+        searches.remove(&search_id);
+    }
+
+    result
+}
+
+#[tauri::command]
+pub fn search_cancel(registry: State<'_, SearchRegistry>, search_id: String) -> bool {
+    match registry.0.lock().unwrap().remove(&search_id) {
+        Some(token) => {
+            token.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    }
+}
@@ -0,0 +1,333 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Mutex};
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use crate::write_atomic;
+
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+static SETTINGS_CACHE: Mutex<Option<Value>> = Mutex::new(None);
+// (path of the workspace settings file, its contents) for the open workspace.
+static WORKSPACE_SETTINGS: Mutex<Option<(PathBuf, Value)>> = Mutex::new(None);
+// Dropped, and so stopped, when another workspace is opened.
+static WORKSPACE_WATCHER: Mutex<Option<notify::RecommendedWatcher>> = Mutex::new(None);
+
+// These name programs to run, so a checked-in workspace file must not be able to set them.
+const GLOBAL_ONLY_KEYS: &[&str] = &["formatters", "ai"];
+
+fn is_global_only(key: &str) -> bool {
+    GLOBAL_ONLY_KEYS.iter().any(|k| {
+        key == *k
+            || key
+                .strip_prefix(k)
+                .is_some_and(|rest| rest.starts_with('.'))
+    })
+}
+
+pub fn load_dir() -> PathBuf {
+    let local_app_data = std::env::var("LOCALAPPDATA").unwrap_or_else(|_| ".".to_string());
+    PathBuf::from(local_app_data).join("load")
+}
+
+pub fn settings_path() -> PathBuf {
+    load_dir().join("settings.json")
+}
+
+fn read_settings(path: &Path) -> Result<Value, String> {
+    let contents = fs::read_to_string(path).map_err(|e| format!("Failed to read file: {}", e))?;
+    let value: Value =
+        serde_json::from_str(&contents).map_err(|e| format!("Invalid settings JSON: {}", e))?;
+    if !value.is_object() {
+        return Err("Settings must be a JSON object".into());
+    }
+    Ok(value)
+}
+
+pub fn cached_settings() -> Value {
+    let mut cache = SETTINGS_CACHE.lock().unwrap();
+    cache
+        .get_or_insert_with(|| {
+            read_settings(&settings_path()).unwrap_or_else(|_| Value::Object(Default::default()))
+        })
+        .clone()
+}
+
+pub fn invalidate_cache() {
+    *SETTINGS_CACHE.lock().unwrap() = None;
+}
+
+// Looks up a flat key first ("ai.save_before_context"), then a nested path.
+fn lookup(settings: &Value, key: &str) -> Option<Value> {
+    if let Some(v) = settings.get(key) {
+        return Some(v.clone());
+    }
+    let mut current = settings;
+    for part in key.split('.') {
+        current = current.get(part)?;
+    }
+    Some(current.clone())
+}
+
+/// Workspace settings win over global ones, which win over each caller's default.
+pub fn get(key: &str) -> Option<Value> {
+    if is_global_only(key) {
+        return lookup(&cached_settings(), key);
+    }
+    let workspace = WORKSPACE_SETTINGS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|(_, values)| lookup(values, key));
+    workspace.or_else(|| lookup(&cached_settings(), key))
+}
+
+pub fn workspace_settings_path(root: &Path) -> PathBuf {
+    root.join(".load").join("settings.json")
+}
+
+fn read_workspace(path: &Path) -> Value {
+    let mut values = if path.exists() {
+        read_settings(path)
+            .map_err(|e| eprintln!("Ignoring {}: {}", path.display(), e))
+            .unwrap_or_else(|_| Value::Object(Default::default()))
+    } else {
+        Value::Object(Default::default())
+    };
+    if let Some(map) = values.as_object_mut() {
+        map.retain(|key, _| {
+            let keep = !is_global_only(key);
+            if !keep {
+                eprintln!(
+                    "Ignoring `{}` in {}: global settings only",
+                    key,
+                    path.display()
+                );
+            }
+            keep
+        });
+    }
+    values
+}
+
+/// Replaces the workspace layer with `root`'s `.load/settings.json`, if it has one.
+pub fn load_workspace(root: &Path) {
+    let path = workspace_settings_path(root);
+    let values = read_workspace(&path);
+    *WORKSPACE_SETTINGS.lock().unwrap() = Some((path, values));
+}
+
+/// Reloads the workspace layer and emits `workspace-settings-changed` whenever `root`'s
+/// settings file changes on disk.
+pub fn watch_workspace(app: AppHandle, root: &Path) {
+    let dir = root.join(".load");
+    let path = workspace_settings_path(root);
+    let (tx, rx) = mpsc::channel();
+    let (watched_dir, watched_file) = (dir.clone(), path.clone());
+    let mut watcher =
+        match notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event
+                    .paths
+                    .iter()
+                    .any(|p| *p == watched_dir || *p == watched_file)
+                {
+                    let _ = tx.send(());
+                }
+            }
+        }) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("Failed to create workspace settings watcher: {}", e);
+                return;
+            }
+        };
+    // The root itself is watched so a `.load` directory created later is noticed.
+    if let Err(e) = watcher.watch(root, RecursiveMode::NonRecursive) {
+        eprintln!("Failed to watch workspace root: {}", e);
+        return;
+    }
+    let mut dir_watched = watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok();
+    *WORKSPACE_WATCHER.lock().unwrap() = Some(watcher);
+
+    std::thread::spawn(move || {
+        while rx.recv().is_ok() {
+            while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+            let values = read_workspace(&path);
+            {
+                let mut guard = WORKSPACE_SETTINGS.lock().unwrap();
+                match guard.as_mut() {
+                    Some((current, layer)) if *current == path => *layer = values.clone(),
+                    _ => return,
+                }
+            }
+            if !dir_watched {
+                if let Some(w) = WORKSPACE_WATCHER.lock().unwrap().as_mut() {
+                    dir_watched = w.watch(&dir, RecursiveMode::NonRecursive).is_ok();
+                }
+            }
+            app.emit("workspace-settings-changed", &values)
+                .map_err(|e| e.to_string())
+                .ok();
+        }
+    });
+}
+
+// Objects merge key by key; any other value in `overlay` replaces the one in `base`.
+fn merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (k, v) in overlay {
+                merge(base.entry(k.clone()).or_insert(Value::Null), v);
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+// RFC 7396 merge patch: `null` removes a key.
+fn apply_patch(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (k, v) in patch {
+                if v.is_null() {
+                    target.remove(k);
+                } else {
+                    apply_patch(target.entry(k.clone()).or_insert(Value::Null), v);
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsLayers {
+    effective: Value,
+    global: Value,
+    /// `None` when no workspace is open.
+    workspace: Option<Value>,
+    workspace_path: Option<String>,
+}
+
+#[tauri::command]
+pub fn effective_settings() -> SettingsLayers {
+    let global = cached_settings();
+    let workspace = WORKSPACE_SETTINGS.lock().unwrap().clone();
+    let mut effective = global.clone();
+    if let Some((_, values)) = &workspace {
+        merge(&mut effective, values);
+    }
+    SettingsLayers {
+        effective,
+        global,
+        workspace_path: workspace
+            .as_ref()
+            .map(|(p, _)| p.to_string_lossy().to_string()),
+        workspace: workspace.map(|(_, v)| v),
+    }
+}
+
+#[tauri::command]
+pub fn update_workspace_settings(app: AppHandle, patch: Value) -> Result<Value, String> {
+    let map = patch
+        .as_object()
+        .ok_or("Settings patch must be a JSON object")?;
+    if let Some(key) = map.keys().find(|k| is_global_only(k)) {
+        return Err(format!("`{}` can only be set in the global settings", key));
+    }
+    let mut guard = WORKSPACE_SETTINGS.lock().unwrap();
+    let (path, values) = guard.as_mut().ok_or("no workspace open")?;
+
+    let mut updated = values.clone();
+    apply_patch(&mut updated, &patch);
+    let contents = serde_json::to_string_pretty(&updated).map_err(|e| e.to_string())?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    write_atomic(path, contents.as_bytes())?;
+    *values = updated.clone();
+    drop(guard);
+
+    app.emit("workspace-settings-changed", &updated)
+        .map_err(|e| e.to_string())
+        .ok();
+    Ok(updated)
+}
+
+pub fn get_bool(key: &str, default: bool) -> bool {
+    get(key).and_then(|v| v.as_bool()).unwrap_or(default)
+}
+
+pub fn watch_settings(app: AppHandle) {
+    std::thread::spawn(move || {
+        let dir = load_dir();
+        let target = settings_path();
+        let (tx, rx) = mpsc::channel();
+
+        let mut watcher =
+            match notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                if let Ok(event) = res {
+                    if event
+                        .paths
+                        .iter()
+                        .any(|p| p.file_name() == Some("settings.json".as_ref()))
+                    {
+                        let _ = tx.send(());
+                    }
+                }
+            }) {
+                Ok(w) => w,
+                Err(e) => {
+                    eprintln!("Failed to create settings watcher: {}", e);
+                    return;
+                }
+            };
+
+        // On a first run nothing has created the directory yet, and it can't be watched
+        // until it exists.
+        if let Err(e) = fs::create_dir_all(&dir) {
+            eprintln!("Failed to create settings directory: {}", e);
+            return;
+        }
+        if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+            eprintln!("Failed to watch settings directory: {}", e);
+            return;
+        }
+
+        while rx.recv().is_ok() {
+            // Editors often write in several steps; wait for the burst to settle.
+            while rx.recv_timeout(DEBOUNCE).is_ok() {}
+
+            invalidate_cache();
+            let payload = match read_settings(&target) {
+                Ok(v) => v,
+                Err(e) => serde_json::json!({ "__error": e }),
+            };
+            app.emit("settings-changed", payload)
+                .map_err(|e| eprintln!("Failed to emit settings-changed event: {}", e))
+                .ok();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_keys_are_global_only() {
+        assert!(is_global_only("formatters"));
+        assert!(is_global_only("formatters.rust"));
+        assert!(is_global_only("ai.save_before_context"));
+        assert!(!is_global_only("aim"));
+        assert!(!is_global_only("tab_width"));
+    }
+}
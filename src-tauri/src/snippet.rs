@@ -0,0 +1,219 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+
+use crate::{edit, history, EditorState, Offset};
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetRange {
+    start: Offset,
+    end: Offset,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TabStop {
+    index: usize,
+    /// Every occurrence of the placeholder; more than one means the others mirror the first.
+    ranges: Vec<SnippetRange>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SnippetExpansion {
+    /// Ordered `$1`, `$2`, ...; `$0` is reported separately as `final_position`.
+    tab_stops: Vec<TabStop>,
+    final_position: Offset,
+}
+
+enum Segment {
+    Text(String),
+    Stop(usize, Option<String>),
+}
+
+fn read_index(chars: &[char], i: &mut usize) -> Option<usize> {
+    let start = *i;
+    while *i < chars.len() && chars[*i].is_ascii_digit() {
+        *i += 1;
+    }
+    chars[start..*i].iter().collect::<String>().parse().ok()
+}
+
+// Understands `$N`, `${N}` and `${N:default}`; `\$`, `\}` and `\\` escape, and anything
+// else that isn't a well-formed placeholder is kept as literal text.
+fn parse(body: &str) -> Vec<Segment> {
+    let chars: Vec<char> = body.chars().collect();
+    let mut segments = Vec::new();
+    let mut text = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\\' && matches!(chars.get(i + 1), Some('$' | '}' | '\\')) {
+            text.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        if c != '$' {
+            text.push(c);
+            i += 1;
+            continue;
+        }
+
+        let mut j = i + 1;
+        let stop = if chars.get(j) == Some(&'{') {
+            j += 1;
+            read_index(&chars, &mut j).and_then(|index| match chars.get(j) {
+                Some('}') => Some((index, None, j + 1)),
+                Some(':') => {
+                    let mut default = String::new();
+                    let mut k = j + 1;
+                    while k < chars.len() && chars[k] != '}' {
+                        if chars[k] == '\\' && matches!(chars.get(k + 1), Some('$' | '}' | '\\')) {
+                            k += 1;
+                        }
+                        default.push(chars[k]);
+                        k += 1;
+                    }
+                    (k < chars.len()).then_some((index, Some(default), k + 1))
+                }
+                _ => None,
+            })
+        } else {
+            read_index(&chars, &mut j).map(|index| (index, None, j))
+        };
+
+        match stop {
+            Some((index, default, next)) => {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+                segments.push(Segment::Stop(index, default));
+                i = next;
+            }
+            None => {
+                text.push('$');
+                i += 1;
+            }
+        }
+    }
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+    segments
+}
+
+// Builds the inserted text, re-indenting continuation lines, while tracking positions
+// relative to the insertion point.
+struct Builder<'a> {
+    origin: &'a Offset,
+    indent: &'a str,
+    text: String,
+    row: usize,
+    col: usize,
+}
+
+impl Builder<'_> {
+    fn push(&mut self, s: &str) {
+        for (i, piece) in s.split('\n').enumerate() {
+            if i > 0 {
+                self.text.push('\n');
+                self.text.push_str(self.indent);
+                self.row += 1;
+                self.col = self.indent.len();
+            }
+            self.text.push_str(piece);
+            self.col += piece.len();
+        }
+    }
+
+    fn position(&self) -> Offset {
+        Offset {
+            row: self.origin.row + self.row,
+            col: if self.row == 0 {
+                self.origin.col + self.col
+            } else {
+                self.col
+            },
+        }
+    }
+}
+
+#[tauri::command]
+pub fn expand_snippet(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    offset: Offset,
+    body: String,
+) -> Result<SnippetExpansion, String> {
+    let mut guard = state.0.lock().unwrap();
+    let file = guard.active_mut().ok_or("no file opened")?;
+    let line = file
+        .lines
+        .get(offset.row)
+        .ok_or_else(|| format!("row {} is out of range", offset.row))?;
+    let indent: String = line
+        .get(..offset.col)
+        .ok_or_else(|| format!("column {} is not a valid position", offset.col))?
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect();
+
+    let segments = parse(&body.replace("\r\n", "\n"));
+    let mut defaults: BTreeMap<usize, String> = BTreeMap::new();
+    for segment in &segments {
+        if let Segment::Stop(index, Some(default)) = segment {
+            defaults.entry(*index).or_insert_with(|| default.clone());
+        }
+    }
+
+    let mut builder = Builder {
+        origin: &offset,
+        indent: &indent,
+        text: String::new(),
+        row: 0,
+        col: 0,
+    };
+    let mut stops: BTreeMap<usize, Vec<SnippetRange>> = BTreeMap::new();
+    for segment in &segments {
+        match segment {
+            Segment::Text(text) => builder.push(text),
+            Segment::Stop(index, _) => {
+                let start = builder.position();
+                builder.push(defaults.get(index).map(|s| s.as_str()).unwrap_or(""));
+                stops.entry(*index).or_default().push(SnippetRange {
+                    start,
+                    end: builder.position(),
+                });
+            }
+        }
+    }
+    let end = builder.position();
+    let text = builder.text;
+
+    let old_total = file.lines.len();
+    let (splice, _) = edit::replace_range(file, &offset, &offset, &text)?;
+    edit::emit_changed(
+        &app,
+        file,
+        splice.start,
+        splice.start + splice.new.len(),
+        old_total,
+    );
+    file.history.push(vec![splice]);
+    history::emit_availability(&app, &mut file.history);
+
+    let final_position = stops
+        .remove(&0)
+        .and_then(|ranges| ranges.into_iter().next())
+        .map(|r| r.start)
+        .unwrap_or(end);
+    Ok(SnippetExpansion {
+        tab_stops: stops
+            .into_iter()
+            .map(|(index, ranges)| TabStop { index, ranges })
+            .collect(),
+        final_position,
+    })
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rayon::prelude::*;
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::{
+    build_gitignore, detect_language_from_extension, dirhash, get_ts_language, is_dot_folder,
+    is_ignored_path, parse_text, read_text_file, resolve_path, tags,
+};
+
+/// Files larger than this are not parsed for symbols.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Indexing stops adding symbols past this many, to bound memory on huge trees.
+const MAX_SYMBOLS: usize = 200_000;
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSymbol {
+    name: String,
+    kind: String,
+    path: String,
+    line: usize,
+    col: usize,
+}
+
+struct SymbolIndex {
+    root: PathBuf,
+    files: HashMap<PathBuf, (SystemTime, Vec<WorkspaceSymbol>)>,
+    // Held only to keep the watcher alive for as long as this index is current.
+    _watcher: Option<RecommendedWatcher>,
+}
+
+static INDEX: Mutex<Option<SymbolIndex>> = Mutex::new(None);
+
+// `None` means the file is not indexable (no grammar, too large, unreadable).
+fn index_file(path: &Path) -> Option<(SystemTime, Vec<WorkspaceSymbol>)> {
+    let language = detect_language_from_extension(&path.to_path_buf());
+    get_ts_language(&language)?;
+    let meta = fs::metadata(path).ok()?;
+    if meta.len() > MAX_FILE_BYTES {
+        return None;
+    }
+    let source = read_text_file(path).ok()?;
+    let tree = parse_text(&language, &source)?;
+    let path = path.to_string_lossy();
+    let mut symbols: Vec<WorkspaceSymbol> = tags::definitions(&language, tree.root_node(), &source)
+        .into_iter()
+        .map(|d| WorkspaceSymbol {
+            name: d.symbol.name,
+            kind: d.symbol.kind,
+            path: path.to_string(),
+            line: d.symbol.start_row,
+            col: d.symbol.start_col,
+        })
+        .collect();
+    symbols.sort_by_key(|s| (s.line, s.col));
+    Some((meta.modified().ok()?, symbols))
+}
+
+// Files are parsed without holding the lock, so searches aren't blocked behind a burst of
+// changes; the results are dropped if the index was rebuilt for another root meanwhile.
+fn refresh_paths(paths: &[PathBuf]) {
+    let root = match INDEX.lock().unwrap().as_ref() {
+        Some(index) => index.root.clone(),
+        None => return,
+    };
+    let matcher = build_gitignore(&root);
+    let updates: Vec<_> = paths
+        .iter()
+        .filter(|path| {
+            let rel = match path.strip_prefix(&root) {
+                Ok(r) => r,
+                Err(_) => return false,
+            };
+            let hidden = rel
+                .parent()
+                .into_iter()
+                .flat_map(|p| p.components())
+                .any(|c| is_dot_folder(&c.as_os_str().to_string_lossy()));
+            !hidden && !is_ignored_path(matcher.as_ref(), &root, path, false)
+        })
+        .map(|path| (path, index_file(path)))
+        .collect();
+
+    let mut guard = INDEX.lock().unwrap();
+    let index = match guard.as_mut() {
+        Some(i) if i.root == root => i,
+        _ => return,
+    };
+    let mut total: usize = index.files.values().map(|(_, s)| s.len()).sum();
+    for (path, entry) in updates {
+        if let Some((_, old)) = index.files.remove(path) {
+            total -= old.len();
+        }
+        if let Some((mtime, mut symbols)) = entry {
+            symbols.truncate(MAX_SYMBOLS.saturating_sub(total));
+            if !symbols.is_empty() {
+                total += symbols.len();
+                index.files.insert(path.clone(), (mtime, symbols));
+            }
+        }
+    }
+}
+
+fn watch_root(root: &Path) -> Option<RecommendedWatcher> {
+    let mut watcher = notify::recommended_watcher(|res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if !event.kind.is_access() {
+                refresh_paths(&event.paths);
+            }
+        }
+    })
+    .map_err(|e| eprintln!("Failed to create symbol watcher: {}", e))
+    .ok()?;
+    watcher
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| eprintln!("Failed to watch {}: {}", root.display(), e))
+        .ok()?;
+    Some(watcher)
+}
+
+fn build_index(root: PathBuf) -> Vec<WorkspaceSymbol> {
+    // Files whose mtime hasn't moved since the last build of the same root are reused.
+    // Taken out before dropping so the old watcher never shuts down while the lock is held.
+    let previous = INDEX.lock().unwrap().take();
+    let previous = match previous {
+        Some(index) if index.root == root => index.files,
+        _ => HashMap::new(),
+    };
+
+    let mut files: Vec<(PathBuf, (SystemTime, Vec<WorkspaceSymbol>))> =
+        dirhash::collect_files(&root)
+            .into_par_iter()
+            .filter_map(|path| {
+                let mtime = fs::metadata(&path).and_then(|m| m.modified()).ok();
+                if let Some(cached) = previous.get(&path).filter(|c| Some(c.0) == mtime) {
+                    return Some((path, cached.clone()));
+                }
+                let entry = index_file(&path)?;
+                Some((path, entry))
+            })
+            .collect();
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut total = 0;
+    files.retain_mut(|(_, (_, symbols))| {
+        symbols.truncate(MAX_SYMBOLS.saturating_sub(total));
+        total += symbols.len();
+        !symbols.is_empty()
+    });
+    let all: Vec<WorkspaceSymbol> = files
+        .iter()
+        .flat_map(|(_, (_, symbols))| symbols.iter().cloned())
+        .collect();
+
+    let watcher = watch_root(&root);
+    *INDEX.lock().unwrap() = Some(SymbolIndex {
+        root,
+        files: files.into_iter().collect(),
+        _watcher: watcher,
+    });
+    all
+}
+
+// Case-insensitive subsequence match; consecutive and word-start hits score higher, so
+// `gws` ranks `get_workspace_symbols` above `changeWorkspaceStatus`.
+fn fuzzy_score(query: &[char], name: &str) -> Option<usize> {
+    let mut score = 0;
+    let mut qi = 0;
+    let mut prev_match = false;
+    let mut prev_char: Option<char> = None;
+    for c in name.chars() {
+        if qi == query.len() {
+            break;
+        }
+        let word_start = prev_char
+            .is_none_or(|p| !p.is_alphanumeric() || (p.is_lowercase() && c.is_uppercase()));
+        if c.to_lowercase().eq(query[qi].to_lowercase()) {
+            score += 1 + if prev_match { 2 } else { 0 } + if word_start { 3 } else { 0 };
+            qi += 1;
+            prev_match = true;
+        } else {
+            prev_match = false;
+        }
+        prev_char = Some(c);
+    }
+    (qi == query.len()).then_some(score)
+}
+
+#[tauri::command]
+pub async fn build_workspace_symbols(
+    app: AppHandle,
+    root: String,
+) -> Result<Vec<WorkspaceSymbol>, String> {
+    let root_pb = resolve_path(&app, &root)?;
+    if !root_pb.is_dir() {
+        return Err("path is not a directory".into());
+    }
+    tokio::task::spawn_blocking(move || build_index(root_pb))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn search_workspace_symbols(
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<WorkspaceSymbol>, String> {
+    let guard = INDEX.lock().unwrap();
+    let index = guard
+        .as_ref()
+        .ok_or("workspace symbols have not been built")?;
+    let query: Vec<char> = query.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let mut matches: Vec<(usize, &WorkspaceSymbol)> = index
+        .files
+        .values()
+        .flat_map(|(_, symbols)| symbols)
+        .filter_map(|s| fuzzy_score(&query, &s.name).map(|score| (score, s)))
+        .collect();
+    matches.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then_with(|| a.1.name.len().cmp(&b.1.name.len()))
+            .then_with(|| a.1.path.cmp(&b.1.path))
+    });
+    Ok(matches
+        .into_iter()
+        .take(limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+        .map(|(_, s)| s.clone())
+        .collect())
+}
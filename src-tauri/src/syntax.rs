@@ -0,0 +1,811 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, State};
+use tree_sitter::{Node, Point, Tree};
+
+use crate::{
+    clamp_line_range, get_ts_language, highlight, indent, parse_buffer, tags, tokenize_range,
+    EditorState, FileState, Offset, Token,
+};
+
+const MAX_TREE_JSON_DEPTH: usize = 64;
+const DEFAULT_HIGHLIGHT: &str = "untokenized";
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StyledSpan {
+    text: String,
+    highlight: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StyledLine {
+    row: usize,
+    spans: Vec<StyledSpan>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseError {
+    start: Offset,
+    end: Offset,
+    message: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineItem {
+    label: String,
+    depth: usize,
+    start_line: usize,
+    end_line: usize,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FoldingRange {
+    start_line: usize,
+    end_line: usize,
+    /// "block" or "comment", or "indent" for ranges taken from the indentation.
+    kind: &'static str,
+}
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentStructure {
+    symbols: Vec<OutlineItem>,
+    folding_ranges: Vec<FoldingRange>,
+    parse_errors: Vec<ParseError>,
+}
+
+fn to_point(offset: &Offset) -> Point {
+    Point {
+        row: offset.row,
+        column: offset.col,
+    }
+}
+
+fn to_offset(point: Point) -> Offset {
+    Offset {
+        row: point.row,
+        col: point.column,
+    }
+}
+
+fn named_node_at(root: Node<'_>, point: Point) -> Option<Node<'_>> {
+    root.named_descendant_for_point_range(point, point)
+}
+
+fn node_to_json(node: Node<'_>, depth: usize, max_depth: usize) -> Value {
+    let mut children = Vec::new();
+    let truncated = depth >= max_depth && node.named_child_count() > 0;
+    if !truncated {
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            children.push(node_to_json(child, depth + 1, max_depth));
+        }
+    }
+
+    serde_json::json!({
+        "kind": node.kind(),
+        "start": to_offset(node.start_position()),
+        "end": to_offset(node.end_position()),
+        "children": children,
+        "truncated": truncated,
+    })
+}
+
+pub fn definition_kinds(language: &str) -> &'static [&'static str] {
+    match language.to_ascii_lowercase().as_str() {
+        "rust" => &[
+            "function_item",
+            "impl_item",
+            "trait_item",
+            "struct_item",
+            "enum_item",
+            "mod_item",
+        ],
+        "javascript" | "typescript" | "tsx" => &[
+            "function_declaration",
+            "generator_function_declaration",
+            "method_definition",
+            "class_declaration",
+            "interface_declaration",
+        ],
+        "python" => &["function_definition", "class_definition"],
+        "go" => &["function_declaration", "method_declaration", "type_spec"],
+        "java" => &[
+            "method_declaration",
+            "constructor_declaration",
+            "class_declaration",
+            "interface_declaration",
+            "enum_declaration",
+        ],
+        "c" | "cpp" => &[
+            "function_definition",
+            "struct_specifier",
+            "class_specifier",
+            "namespace_definition",
+        ],
+        "ruby" => &["method", "singleton_method", "class", "module"],
+        "php" => &[
+            "function_definition",
+            "method_declaration",
+            "class_declaration",
+        ],
+        "lua" => &["function_declaration"],
+        "bash" => &["function_definition"],
+        _ => &[
+            "function_declaration",
+            "function_definition",
+            "method_declaration",
+            "class_declaration",
+        ],
+    }
+}
+
+pub fn definition_name(node: Node<'_>, source: &str) -> Option<String> {
+    let name_node = node
+        .child_by_field_name("name")
+        .or_else(|| node.child_by_field_name("type"))
+        .or_else(|| {
+            // C-style definitions nest the identifier inside declarators.
+            let mut decl = node.child_by_field_name("declarator")?;
+            while let Some(inner) = decl.child_by_field_name("declarator") {
+                decl = inner;
+            }
+            Some(decl)
+        })?;
+    name_node
+        .utf8_text(source.as_bytes())
+        .ok()
+        .map(|s| s.to_string())
+}
+
+fn find_node_of_kind<'a>(
+    node: Node<'a>,
+    kind: &str,
+    point: Point,
+    backward: bool,
+    found: &mut Option<Node<'a>>,
+) {
+    if !backward && node.end_position() <= point {
+        return;
+    }
+    if backward && node.start_position() >= point {
+        return;
+    }
+
+    if node.kind() == kind {
+        let start = node.start_position();
+        if !backward && start > point {
+            if found.is_none_or(|f| start < f.start_position()) {
+                *found = Some(node);
+            }
+            return;
+        }
+        if backward && start < point && found.is_none_or(|f| start > f.start_position()) {
+            *found = Some(node);
+        }
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        find_node_of_kind(child, kind, point, backward, found);
+    }
+}
+
+// Missing nodes are tokens the parser inserted to recover; error nodes wrap input it
+// had to skip.
+fn error_message(node: Node<'_>) -> Option<String> {
+    if node.is_missing() {
+        Some(format!("missing `{}`", node.kind()))
+    } else if node.is_error() {
+        Some(match node.child(0) {
+            Some(child) => format!("unexpected `{}`", child.kind()),
+            None => "unexpected input".to_string(),
+        })
+    } else {
+        None
+    }
+}
+
+fn collect_parse_errors(node: Node<'_>, errors: &mut Vec<ParseError>) {
+    if !node.has_error() {
+        return;
+    }
+    if let Some(message) = error_message(node) {
+        errors.push(ParseError {
+            start: to_offset(node.start_position()),
+            end: to_offset(node.end_position()),
+            message,
+        });
+        return;
+    }
+
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_parse_errors(child, errors);
+    }
+}
+
+// False for languages without a grammar.
+pub fn has_parse_errors(file: &FileState) -> bool {
+    parse_buffer(file).is_some_and(|tree| tree.root_node().has_error())
+}
+
+#[tauri::command]
+pub fn buffer_has_syntax_errors(state: State<'_, EditorState>) -> Result<bool, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    Ok(has_parse_errors(file))
+}
+
+#[tauri::command]
+pub fn get_parse_errors(state: State<'_, EditorState>) -> Result<Vec<ParseError>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let mut errors = Vec::new();
+    if let Some(tree) = parse_buffer(file) {
+        collect_parse_errors(tree.root_node(), &mut errors);
+    }
+    Ok(errors)
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    start_offset: Offset,
+    end_offset: Offset,
+    message: String,
+}
+
+fn collect_diagnostics(node: Node<'_>, start: usize, end: usize, out: &mut Vec<Diagnostic>) {
+    if !node.has_error() || node.end_position().row < start || node.start_position().row > end {
+        return;
+    }
+    if let Some(message) = error_message(node) {
+        out.push(Diagnostic {
+            start_offset: to_offset(node.start_position()),
+            end_offset: to_offset(node.end_position()),
+            message,
+        });
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_diagnostics(child, start, end, out);
+    }
+}
+
+#[tauri::command]
+pub fn request_diagnostics(
+    app: AppHandle,
+    state: State<'_, EditorState>,
+    line_start: usize,
+    line_end: usize,
+) -> Result<(), String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let (start, end) = clamp_line_range(file.lines.len(), line_start, line_end);
+    let mut diagnostics = Vec::new();
+    if let Some(tree) = parse_buffer(file) {
+        collect_diagnostics(tree.root_node(), start, end, &mut diagnostics);
+    }
+    app.emit("diagnostics", &diagnostics)
+        .map_err(|e| e.to_string())
+        .ok();
+    Ok(())
+}
+
+#[tauri::command]
+pub fn next_sibling_node(
+    state: State<'_, EditorState>,
+    offset: Offset,
+    backward: Option<bool>,
+) -> Result<Option<Offset>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let tree = match parse_buffer(file) {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let backward = backward.unwrap_or(false);
+
+    let mut node = named_node_at(tree.root_node(), to_point(&offset));
+    while let Some(n) = node {
+        let sibling = if backward {
+            n.prev_named_sibling()
+        } else {
+            n.next_named_sibling()
+        };
+        if let Some(s) = sibling {
+            return Ok(Some(to_offset(s.start_position())));
+        }
+        node = n.parent();
+    }
+
+    Ok(None)
+}
+
+#[tauri::command]
+pub fn enclosing_scope(
+    state: State<'_, EditorState>,
+    offset: Offset,
+) -> Result<Option<String>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let tree = match parse_buffer(file) {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let source = file.lines.join("\n");
+    let kinds = definition_kinds(&file.language);
+
+    let mut node = named_node_at(tree.root_node(), to_point(&offset));
+    while let Some(n) = node {
+        if kinds.contains(&n.kind()) {
+            if let Some(name) = definition_name(n, &source) {
+                return Ok(Some(name));
+            }
+        }
+        node = n.parent();
+    }
+
+    Ok(None)
+}
+
+// Statement-level kinds that don't follow the grammars' common `*_statement` naming.
+fn statement_kinds(language: &str) -> &'static [&'static str] {
+    match language {
+        "rust" => &[
+            "let_declaration",
+            "use_declaration",
+            "const_item",
+            "static_item",
+        ],
+        "javascript" | "typescript" | "tsx" => &["lexical_declaration", "variable_declaration"],
+        "go" => &[
+            "short_var_declaration",
+            "var_declaration",
+            "const_declaration",
+        ],
+        "c" | "cpp" => &["declaration"],
+        "java" => &["local_variable_declaration", "field_declaration"],
+        "lua" => &["variable_declaration", "local_variable_declaration"],
+        "zig" => &["variable_declaration"],
+        _ => &[],
+    }
+}
+
+/// Range of the innermost statement around `offset`, extended over a `;` or `,` that
+/// the grammar left as the next sibling.
+#[tauri::command]
+pub fn statement_range_at(
+    state: State<'_, EditorState>,
+    offset: Offset,
+) -> Result<Option<(Offset, Offset)>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let tree = match parse_buffer(file) {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let kinds = statement_kinds(&file.language);
+
+    let mut node = named_node_at(tree.root_node(), to_point(&offset));
+    while let Some(n) = node {
+        if n.kind().ends_with("_statement") || kinds.contains(&n.kind()) {
+            let end = match n.next_sibling() {
+                Some(next) if !next.is_named() && matches!(next.kind(), ";" | ",") => {
+                    next.end_position()
+                }
+                _ => n.end_position(),
+            };
+            return Ok(Some((to_offset(n.start_position()), to_offset(end))));
+        }
+        node = n.parent();
+    }
+
+    Ok(None)
+}
+
+#[tauri::command]
+pub fn next_node_of_kind(
+    state: State<'_, EditorState>,
+    offset: Offset,
+    kind: String,
+    backward: Option<bool>,
+) -> Result<Option<Offset>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let tree = match parse_buffer(file) {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+
+    let mut found = None;
+    find_node_of_kind(
+        tree.root_node(),
+        &kind,
+        to_point(&offset),
+        backward.unwrap_or(false),
+        &mut found,
+    );
+    Ok(found.map(|n| to_offset(n.start_position())))
+}
+
+#[tauri::command]
+pub fn parse_tree_json(
+    state: State<'_, EditorState>,
+    max_depth: Option<usize>,
+) -> Result<Value, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    if get_ts_language(&file.language).is_none() {
+        return Err(format!("no grammar available for {}", file.language));
+    }
+    let tree = parse_buffer(file).ok_or("failed to parse buffer")?;
+    let max_depth = max_depth.unwrap_or(8).min(MAX_TREE_JSON_DEPTH);
+    Ok(node_to_json(tree.root_node(), 0, max_depth))
+}
+
+fn push_span(spans: &mut Vec<StyledSpan>, text: &str, highlight: &str) {
+    if text.is_empty() {
+        return;
+    }
+    if let Some(last) = spans.last_mut() {
+        if last.highlight == highlight {
+            last.text.push_str(text);
+            return;
+        }
+    }
+    spans.push(StyledSpan {
+        text: text.to_string(),
+        highlight: highlight.to_string(),
+    });
+}
+
+fn style_line(row: usize, line: &str, tokens: &[Token]) -> StyledLine {
+    let mut ranges: Vec<(usize, usize, &str)> = tokens
+        .iter()
+        .filter(|t| t.start_offset.row <= row && t.end_offset.row >= row)
+        .map(|t| {
+            let start = if t.start_offset.row == row {
+                t.start_offset.col
+            } else {
+                0
+            };
+            let end = if t.end_offset.row == row {
+                t.end_offset.col
+            } else {
+                line.len()
+            };
+            (start.min(line.len()), end.min(line.len()), t.kind.as_str())
+        })
+        .filter(|(s, e, _)| s < e)
+        .collect();
+    ranges.sort_by_key(|(s, e, _)| (*s, *e));
+
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for (start, end, highlight) in ranges {
+        if end <= pos {
+            continue;
+        }
+        let start = start.max(pos);
+        if let (Some(gap), Some(text)) = (line.get(pos..start), line.get(start..end)) {
+            push_span(&mut spans, gap, DEFAULT_HIGHLIGHT);
+            push_span(&mut spans, text, highlight);
+            pos = end;
+        }
+    }
+    push_span(&mut spans, line.get(pos..).unwrap_or(""), DEFAULT_HIGHLIGHT);
+
+    StyledLine { row, spans }
+}
+
+#[tauri::command]
+pub fn styled_lines(
+    state: State<'_, EditorState>,
+    line_start: usize,
+    line_end: usize,
+) -> Result<Vec<StyledLine>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    if file.lines.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let (start, end) = clamp_line_range(file.lines.len(), line_start, line_end);
+    let tree = parse_buffer(file);
+    let tokens = tokenize_range(&file.language, tree.as_ref(), &file.lines, start, end);
+
+    Ok((start..=end)
+        .map(|row| style_line(row, &file.lines[row], &tokens))
+        .collect())
+}
+
+#[derive(Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    #[serde(default)]
+    line_numbers: bool,
+}
+
+const EXPORT_CSS: &str = "\
+pre.load-code{background:#1e1e1e;color:#d4d4d4;padding:1em;font-family:monospace;}\
+.ln{display:inline-block;min-width:3em;color:#858585;user-select:none;}\
+.comment{color:#6a9955;}.string{color:#ce9178;}.escape{color:#d7ba7d;}\
+.number,.boolean,.constant{color:#b5cea8;}.keyword{color:#569cd6;}\
+.type,.constructor,.module{color:#4ec9b0;}.function{color:#dcdcaa;}\
+.property,.attribute,.variable{color:#9cdcfe;}.tag{color:#569cd6;}\
+.punctuation,.operator,.label{color:#d4d4d4;}";
+
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+}
+
+fn render_html(lines: &[StyledLine], opts: &ExportOptions) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><style>");
+    out.push_str(EXPORT_CSS);
+    out.push_str("</style></head><body><pre class=\"load-code\"><code>");
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        if opts.line_numbers {
+            out.push_str(&format!("<span class=\"ln\">{}</span>", line.row + 1));
+        }
+        for span in &line.spans {
+            let class = highlight::highlight_for_kind(&span.highlight);
+            if class == DEFAULT_HIGHLIGHT {
+                escape_html(&span.text, &mut out);
+            } else {
+                out.push_str(&format!("<span class=\"{}\">", class));
+                escape_html(&span.text, &mut out);
+                out.push_str("</span>");
+            }
+        }
+    }
+    out.push_str("</code></pre></body></html>\n");
+    out
+}
+
+#[tauri::command]
+pub fn export_html(
+    state: State<'_, EditorState>,
+    opts: Option<ExportOptions>,
+) -> Result<String, String> {
+    let opts = opts.unwrap_or_default();
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+
+    let end = file.lines.len().saturating_sub(1);
+    let tree = parse_buffer(file);
+    let tokens = tokenize_range(&file.language, tree.as_ref(), &file.lines, 0, end);
+    let lines: Vec<StyledLine> = file
+        .lines
+        .iter()
+        .enumerate()
+        .map(|(row, line)| style_line(row, line, &tokens))
+        .collect();
+    Ok(render_html(&lines, &opts))
+}
+
+#[tauri::command]
+pub fn language_node_kinds(language: String) -> Result<Vec<String>, String> {
+    let ts_language = get_ts_language(&language)
+        .ok_or_else(|| format!("no grammar available for {}", language))?;
+    let kinds: BTreeSet<String> = (0..ts_language.node_kind_count() as u16)
+        .filter(|&id| ts_language.node_kind_is_named(id) && ts_language.node_kind_is_visible(id))
+        .filter_map(|id| ts_language.node_kind_for_id(id))
+        .map(|kind| kind.to_string())
+        .collect();
+    Ok(kinds.into_iter().collect())
+}
+
+// Nests each definition under the innermost earlier one that encloses it.
+fn outline_items(language: &str, root: Node<'_>, source: &str) -> Vec<OutlineItem> {
+    let mut defs = tags::definitions(language, root, source);
+    defs.sort_by_key(|d| (d.start_byte, std::cmp::Reverse(d.end_byte)));
+    let mut open: Vec<usize> = Vec::new();
+    defs.into_iter()
+        .map(|d| {
+            while open.last().is_some_and(|end| *end <= d.start_byte) {
+                open.pop();
+            }
+            let depth = open.len();
+            open.push(d.end_byte);
+            OutlineItem {
+                label: d.symbol.name,
+                depth,
+                start_line: d.symbol.start_row,
+                end_line: d.symbol.end_row,
+            }
+        })
+        .collect()
+}
+
+// A non-blank line followed by a more deeply indented one opens a block that runs until
+// the next non-blank line indented no deeper than the opener.
+fn indentation_outline(lines: &[String], tab_width: usize) -> Vec<OutlineItem> {
+    let mut items: Vec<OutlineItem> = Vec::new();
+    let mut open: Vec<(usize, usize)> = Vec::new(); // (opener indent, item index)
+    let mut prev: Option<(usize, usize)> = None; // (row, indent) of the last non-blank line
+
+    for (row, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let width = indent::indent_width(line, tab_width);
+        while let Some(&(opener, idx)) = open.last() {
+            if opener < width {
+                break;
+            }
+            items[idx].end_line = prev.map(|(r, _)| r).unwrap_or(row);
+            open.pop();
+        }
+        if let Some((prev_row, prev_width)) = prev {
+            if width > prev_width {
+                open.push((prev_width, items.len()));
+                items.push(OutlineItem {
+                    label: lines[prev_row].trim().to_string(),
+                    depth: open.len() - 1,
+                    start_line: prev_row,
+                    end_line: prev_row,
+                });
+            }
+        }
+        prev = Some((row, width));
+    }
+
+    let last = prev.map(|(r, _)| r).unwrap_or(0);
+    for (_, idx) in open {
+        items[idx].end_line = last;
+    }
+    items
+}
+
+// Uses the grammar's definitions when it finds any, otherwise falls back to indentation.
+#[tauri::command]
+pub fn structure_outline(state: State<'_, EditorState>) -> Result<Vec<OutlineItem>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+
+    if let Some(tree) = parse_buffer(file) {
+        let source = file.lines.join("\n");
+        let items = outline_items(&file.language, tree.root_node(), &source);
+        if !items.is_empty() {
+            return Ok(items);
+        }
+    }
+    Ok(indentation_outline(
+        &file.lines,
+        indent::effective_indent(file).tab_width,
+    ))
+}
+
+#[tauri::command]
+pub fn document_structure(state: State<'_, EditorState>) -> Result<DocumentStructure, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+
+    let mut structure = DocumentStructure::default();
+    let tree = parse_buffer(file);
+    if let Some(tree) = &tree {
+        let source = file.lines.join("\n");
+        structure.symbols = outline_items(&file.language, tree.root_node(), &source);
+        collect_parse_errors(tree.root_node(), &mut structure.parse_errors);
+    }
+    structure.folding_ranges = folding_ranges(file, tree.as_ref());
+
+    // Without a grammar (or definitions) the indentation blocks stand in for the symbols.
+    if structure.symbols.is_empty() {
+        structure.symbols =
+            indentation_outline(&file.lines, indent::effective_indent(file).tab_width);
+    }
+    Ok(structure)
+}
+
+// `compound_statement` and the `*_list` kinds are how C and Rust name their brace bodies.
+fn fold_kind(kind: &str) -> Option<&'static str> {
+    if kind.contains("comment") {
+        Some("comment")
+    } else if kind.contains("block")
+        || kind.contains("body")
+        || kind.ends_with("declaration_list")
+        || kind == "compound_statement"
+    {
+        Some("block")
+    } else {
+        None
+    }
+}
+
+fn collect_folds(
+    node: Node<'_>,
+    folds: &mut BTreeMap<usize, (usize, &'static str)>,
+    comments: &mut Vec<(usize, usize)>,
+) {
+    if node.is_named() && node.parent().is_some() {
+        let (start, end) = (node.start_position().row, node.end_position().row);
+        match fold_kind(node.kind()) {
+            Some("comment") => comments.push((start, end)),
+            Some(kind) if end > start => {
+                let fold = folds.entry(start).or_insert((end, kind));
+                fold.0 = fold.0.max(end);
+            }
+            _ => {}
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        collect_folds(child, folds, comments);
+    }
+}
+
+// Runs of line comments on consecutive rows fold as one region.
+fn merge_comment_runs(mut comments: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    comments.sort();
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (start, end) in comments {
+        match runs.last_mut() {
+            Some(run) if start <= run.1 + 1 => run.1 = run.1.max(end),
+            _ => runs.push((start, end)),
+        }
+    }
+    runs.retain(|(start, end)| end > start);
+    runs
+}
+
+// Shared by `document_structure` and `request_fold_ranges`. The indentation blocks are
+// used when there is no grammar or it finds nothing to fold.
+fn folding_ranges(file: &FileState, tree: Option<&Tree>) -> Vec<FoldingRange> {
+    if let Some(tree) = tree {
+        let mut folds = BTreeMap::new();
+        let mut comments = Vec::new();
+        collect_folds(tree.root_node(), &mut folds, &mut comments);
+        for (start, end) in merge_comment_runs(comments) {
+            folds.entry(start).or_insert((end, "comment"));
+        }
+        if !folds.is_empty() {
+            return folds
+                .into_iter()
+                .map(|(start_line, (end_line, kind))| FoldingRange {
+                    start_line,
+                    end_line,
+                    kind,
+                })
+                .collect();
+        }
+    }
+    indentation_outline(&file.lines, indent::effective_indent(file).tab_width)
+        .iter()
+        .filter(|b| b.end_line > b.start_line)
+        .map(|b| FoldingRange {
+            start_line: b.start_line,
+            end_line: b.end_line,
+            kind: "indent",
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn request_fold_ranges(state: State<'_, EditorState>) -> Result<Vec<FoldingRange>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    Ok(folding_ranges(file, parse_buffer(file).as_ref()))
+}
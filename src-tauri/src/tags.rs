@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::State;
+use tree_sitter::{Node, Query, QueryCursor, StreamingIterator};
+
+use crate::{get_ts_language, parse_buffer, syntax, EditorState};
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub kind: String,
+    pub start_row: usize,
+    pub start_col: usize,
+    pub end_row: usize,
+    pub end_col: usize,
+    children: Vec<DocumentSymbol>,
+}
+
+static QUERIES: Mutex<Option<HashMap<String, Option<Arc<Query>>>>> = Mutex::new(None);
+
+// As with highlighting, TypeScript's tags only cover what it adds to JavaScript.
+fn tags_source(language: &str) -> Option<String> {
+    let js = tree_sitter_javascript::TAGS_QUERY;
+    Some(match language {
+        "rust" => tree_sitter_rust::TAGS_QUERY.to_string(),
+        "javascript" => js.to_string(),
+        "typescript" | "tsx" => format!("{}\n{}", tree_sitter_typescript::TAGS_QUERY, js),
+        "python" => tree_sitter_python::TAGS_QUERY.to_string(),
+        "go" => tree_sitter_go::TAGS_QUERY.to_string(),
+        "java" => tree_sitter_java::TAGS_QUERY.to_string(),
+        "c" => tree_sitter_c::TAGS_QUERY.to_string(),
+        "cpp" => tree_sitter_cpp::TAGS_QUERY.to_string(),
+        "php" => tree_sitter_php::TAGS_QUERY.to_string(),
+        "ruby" => tree_sitter_ruby::TAGS_QUERY.to_string(),
+        "ocaml" => tree_sitter_ocaml::TAGS_QUERY.to_string(),
+        "swift" => tree_sitter_swift::TAGS_QUERY.to_string(),
+        "lua" => tree_sitter_lua::TAGS_QUERY.to_string(),
+        _ => return None,
+    })
+}
+
+fn tags_query(language: &str) -> Option<Arc<Query>> {
+    let language = language.to_ascii_lowercase();
+    let mut guard = QUERIES.lock().unwrap();
+    let cache = guard.get_or_insert_with(HashMap::new);
+    if let Some(cached) = cache.get(&language) {
+        return cached.clone();
+    }
+    let compiled = tags_source(&language).and_then(|source| {
+        let ts_language = get_ts_language(&language)?;
+        match Query::new(&ts_language, &source) {
+            Ok(query) => Some(Arc::new(query)),
+            Err(e) => {
+                eprintln!("Failed to compile {} tags query: {}", language, e);
+                None
+            }
+        }
+    });
+    cache.insert(language, compiled.clone());
+    compiled
+}
+
+pub struct Definition {
+    pub start_byte: usize,
+    pub end_byte: usize,
+    pub symbol: DocumentSymbol,
+}
+
+fn definition(node: Node<'_>, name: String, kind: &str) -> Definition {
+    let (start, end) = (node.start_position(), node.end_position());
+    Definition {
+        start_byte: node.start_byte(),
+        end_byte: node.end_byte(),
+        symbol: DocumentSymbol {
+            name,
+            kind: kind.to_string(),
+            start_row: start.row,
+            start_col: start.column,
+            end_row: end.row,
+            end_col: end.column,
+            children: Vec::new(),
+        },
+    }
+}
+
+// A node matched by several patterns (a Rust method also matches the plain function
+// pattern) keeps the earliest one, which the tags queries list as the more specific.
+fn query_definitions(query: &Query, root: Node<'_>, source: &str) -> Vec<Definition> {
+    let names = query.capture_names();
+    let mut chosen: HashMap<(usize, usize), (usize, Definition)> = HashMap::new();
+    let mut cursor = QueryCursor::new();
+    let mut matches = cursor.matches(query, root, source.as_bytes());
+    while let Some(m) = matches.next() {
+        let mut name = None;
+        let mut def = None;
+        for capture in m.captures {
+            let capture_name = names[capture.index as usize];
+            if capture_name == "name" {
+                name = capture.node.utf8_text(source.as_bytes()).ok();
+            } else if let Some(kind) = capture_name.strip_prefix("definition.") {
+                def = Some((capture.node, kind));
+            }
+        }
+        let ((node, kind), name) = match (def, name) {
+            (Some(d), Some(n)) => (d, n),
+            _ => continue,
+        };
+        let key = (node.start_byte(), node.end_byte());
+        if chosen.get(&key).is_some_and(|(p, _)| *p <= m.pattern_index) {
+            continue;
+        }
+        chosen.insert(
+            key,
+            (m.pattern_index, definition(node, name.to_string(), kind)),
+        );
+    }
+    chosen.into_values().map(|(_, d)| d).collect()
+}
+
+fn kind_definitions(node: Node<'_>, kinds: &[&str], source: &str, out: &mut Vec<Definition>) {
+    if kinds.contains(&node.kind()) {
+        if let Some(name) = syntax::definition_name(node, source) {
+            out.push(definition(node, name, node.kind()));
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        kind_definitions(child, kinds, source, out);
+    }
+}
+
+fn close(stack: &mut Vec<(usize, DocumentSymbol)>, roots: &mut Vec<DocumentSymbol>) {
+    if let Some((_, done)) = stack.pop() {
+        match stack.last_mut() {
+            Some((_, parent)) => parent.children.push(done),
+            None => roots.push(done),
+        }
+    }
+}
+
+/// Every definition under `root`, unnested and in no particular order. Grammars without a
+/// tags query fall back to the definition kinds the outline has always used.
+pub fn definitions(language: &str, root: Node<'_>, source: &str) -> Vec<Definition> {
+    match tags_query(language) {
+        Some(query) => query_definitions(&query, root, source),
+        None => {
+            let mut defs = Vec::new();
+            kind_definitions(root, syntax::definition_kinds(language), source, &mut defs);
+            defs
+        }
+    }
+}
+
+// Definitions arrive flat; each becomes a child of the innermost earlier one enclosing it.
+fn nest(mut defs: Vec<Definition>) -> Vec<DocumentSymbol> {
+    defs.sort_by_key(|d| (d.start_byte, std::cmp::Reverse(d.end_byte)));
+    let mut roots: Vec<DocumentSymbol> = Vec::new();
+    let mut stack: Vec<(usize, DocumentSymbol)> = Vec::new();
+    for def in defs {
+        while stack.last().is_some_and(|(end, _)| *end <= def.start_byte) {
+            close(&mut stack, &mut roots);
+        }
+        stack.push((def.end_byte, def.symbol));
+    }
+    while !stack.is_empty() {
+        close(&mut stack, &mut roots);
+    }
+    roots
+}
+
+#[tauri::command]
+pub fn document_symbols(state: State<'_, EditorState>) -> Result<Vec<DocumentSymbol>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    let tree = match parse_buffer(file) {
+        Some(t) => t,
+        None => return Ok(Vec::new()),
+    };
+    let source = file.lines.join("\n");
+    Ok(nest(definitions(&file.language, tree.root_node(), &source)))
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+use notify::{RecursiveMode, Watcher};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use crate::resolve_path;
+
+// Also bounds how long `untail_file` takes to be noticed, and catches writes the
+// watcher misses (network drives, some editors' rename-over saves).
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Default)]
+pub struct TailRegistry(Mutex<HashMap<PathBuf, Arc<AtomicBool>>>);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AppendedPayload {
+    path: String,
+    lines: Vec<String>,
+    /// The file shrank (truncated or rotated) and is being re-read from the start.
+    reset: bool,
+}
+
+struct Tail {
+    path: PathBuf,
+    offset: u64,
+    /// Bytes after the last line break, held back until the line is complete.
+    partial: Vec<u8>,
+}
+
+impl Tail {
+    // The file is reopened each time so a rotated log is picked up under its old name.
+    fn read_new(&mut self) -> Option<AppendedPayload> {
+        let len = fs::metadata(&self.path).ok()?.len();
+        let reset = len < self.offset;
+        if reset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset && !reset {
+            return None;
+        }
+
+        let mut file = File::open(&self.path).ok()?;
+        file.seek(SeekFrom::Start(self.offset)).ok()?;
+        let mut chunk = Vec::new();
+        file.take(len - self.offset).read_to_end(&mut chunk).ok()?;
+        self.offset += chunk.len() as u64;
+        self.partial.extend_from_slice(&chunk);
+
+        let complete = match self.partial.iter().rposition(|b| *b == b'\n') {
+            Some(i) => self.partial.drain(..=i).collect::<Vec<u8>>(),
+            None if reset => Vec::new(),
+            None => return None,
+        };
+        let lines = String::from_utf8_lossy(&complete)
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        Some(AppendedPayload {
+            path: self.path.to_string_lossy().to_string(),
+            lines,
+            reset,
+        })
+    }
+}
+
+fn watch(app: AppHandle, path: PathBuf, start: u64, stop: Arc<AtomicBool>) {
+    let (tx, rx) = mpsc::channel();
+    let name = path.file_name().map(|n| n.to_os_string());
+    let mut watcher =
+        match notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event.paths.iter().any(|p| p.file_name() == name.as_deref()) {
+                    let _ = tx.send(());
+                }
+            }
+        }) {
+            Ok(w) => w,
+            Err(e) => {
+                eprintln!("Failed to create tail watcher: {}", e);
+                return;
+            }
+        };
+    // Watching the directory keeps events flowing when the file is deleted and recreated.
+    let dir = path.parent().unwrap_or(Path::new("."));
+    if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+        eprintln!("Failed to watch {}: {}", dir.display(), e);
+        return;
+    }
+
+    let mut tail = Tail {
+        path,
+        offset: start,
+        partial: Vec::new(),
+    };
+    while !stop.load(Ordering::Relaxed) {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(()) | Err(mpsc::RecvTimeoutError::Timeout) => {}
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        }
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        if let Some(payload) = tail.read_new() {
+            app.emit("file-appended", payload)
+                .map_err(|e| e.to_string())
+                .ok();
+        }
+    }
+}
+
+#[tauri::command]
+pub fn tail_file(
+    app: AppHandle,
+    registry: State<'_, TailRegistry>,
+    path: String,
+) -> Result<(), String> {
+    let pb = resolve_path(&app, &path)?;
+    let len = fs::metadata(&pb).map_err(|e| e.to_string())?.len();
+    if !pb.is_file() {
+        return Err("path is not a file".into());
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = registry.0.lock().unwrap().insert(pb.clone(), stop.clone()) {
+        previous.store(true, Ordering::Relaxed);
+    }
+    std::thread::spawn(move || watch(app, pb, len, stop));
+    Ok(())
+}
+
+#[tauri::command]
+pub fn untail_file(
+    app: AppHandle,
+    registry: State<'_, TailRegistry>,
+    path: String,
+) -> Result<bool, String> {
+    let pb = resolve_path(&app, &path)?;
+    Ok(match registry.0.lock().unwrap().remove(&pb) {
+        Some(stop) => {
+            stop.store(true, Ordering::Relaxed);
+            true
+        }
+        None => false,
+    })
+}
@@ -1,7 +1,8 @@
 use std::sync::{
-    mpsc::{self, Receiver, Sender},
+    mpsc::{self, Receiver, RecvTimeoutError, Sender},
     Arc, Mutex,
 };
+use std::time::Duration;
 
 #[derive(Clone)]
 pub struct TaskPool {
@@ -31,8 +32,53 @@ impl TaskPool {
         tasks.drain(..).collect()
     }
 
-    pub fn wait_for_task(&self) {
+    // Returns after a notification or once `idle` elapses, so callers re-check
+    // `fetch_tasks` even if a notification was never delivered. A closed channel
+    // counts as no notification rather than taking the loop down with it.
+    pub fn wait_for_task(&self, idle: Duration) -> bool {
         let rx = self.receiver.lock().unwrap();
-        rx.recv().expect("Failed to receive task event");
+        match rx.recv_timeout(idle) {
+            Ok(()) => true,
+            Err(RecvTimeoutError::Timeout) => false,
+            Err(RecvTimeoutError::Disconnected) => {
+                drop(rx);
+                std::thread::sleep(idle);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn task_without_notification_is_picked_up_after_idle_interval() {
+        let pool = TaskPool::new();
+        pool.tasks.lock().unwrap().push("lost".to_string());
+
+        let idle = Duration::from_millis(50);
+        let started = Instant::now();
+        let mut processed = Vec::new();
+        while processed.is_empty() && started.elapsed() < idle * 4 {
+            pool.wait_for_task(idle);
+            processed = pool.fetch_tasks();
+        }
+        assert_eq!(processed, vec!["lost".to_string()]);
+        assert!(started.elapsed() < idle * 4);
+    }
+
+    #[test]
+    fn disconnected_channel_does_not_panic() {
+        let (tx, rx) = mpsc::channel();
+        drop(tx);
+        let pool = TaskPool {
+            tasks: Arc::new(Mutex::new(Vec::new())),
+            notifier: mpsc::channel().0,
+            receiver: Arc::new(Mutex::new(rx)),
+        };
+        assert!(!pool.wait_for_task(Duration::from_millis(1)));
     }
 }
@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{highlight, settings, write_atomic};
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ThemeInfo {
+    name: String,
+    path: String,
+}
+
+fn themes_dir() -> PathBuf {
+    settings::load_dir().join("themes")
+}
+
+fn theme_path(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err("invalid theme name".into());
+    }
+    Ok(themes_dir().join(format!("{}.json", name)))
+}
+
+fn unknown_keys(theme: &Value) -> Vec<String> {
+    theme
+        .as_object()
+        .map(|obj| {
+            obj.keys()
+                .filter(|k| !highlight::is_known_highlight(k))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn list_themes() -> Vec<ThemeInfo> {
+    let mut themes: Vec<ThemeInfo> = fs::read_dir(themes_dir())
+        .map(|rd| {
+            rd.flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("json"))
+                .filter_map(|p| {
+                    let name = p.file_stem()?.to_str()?.to_string();
+                    Some(ThemeInfo {
+                        name,
+                        path: p.to_string_lossy().to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    themes.sort_by_key(|t| t.name.to_lowercase());
+    themes
+}
+
+#[tauri::command]
+pub fn load_theme(name: String) -> Result<Value, String> {
+    let contents = fs::read_to_string(theme_path(&name)?).map_err(|e| e.to_string())?;
+    let theme: Value = serde_json::from_str(&contents).map_err(|e| e.to_string())?;
+    if !theme.is_object() {
+        return Err("theme must be a JSON object".into());
+    }
+    let unknown = unknown_keys(&theme);
+    if !unknown.is_empty() {
+        eprintln!("Theme {} has unknown highlight names: {:?}", name, unknown);
+    }
+    Ok(theme)
+}
+
+// Returns the keys that don't match a known highlight name, so the UI can warn about them.
+#[tauri::command]
+pub fn save_theme(name: String, data: Value) -> Result<Vec<String>, String> {
+    if !data.is_object() {
+        return Err("theme must be a JSON object".into());
+    }
+    let path = theme_path(&name)?;
+    fs::create_dir_all(themes_dir()).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&data).map_err(|e| e.to_string())?;
+    write_atomic(&path, json.as_bytes())?;
+    Ok(unknown_keys(&data))
+}
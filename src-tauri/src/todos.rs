@@ -0,0 +1,156 @@
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::{AppHandle, State};
+use tree_sitter::Node;
+
+use crate::{
+    detect_language_from_extension, parse_text, read_text_file, resolve_path, settings, walk_files,
+    EditorState,
+};
+
+const DEFAULT_MARKERS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoItem {
+    path: String,
+    line: usize,
+    col: usize,
+    marker: String,
+    text: String,
+}
+
+fn markers() -> Vec<String> {
+    settings::get("todo_markers")
+        .and_then(|v| {
+            v.as_array().map(|a| {
+                a.iter()
+                    .filter_map(|m| m.as_str().map(|s| s.to_string()))
+                    .collect::<Vec<_>>()
+            })
+        })
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| DEFAULT_MARKERS.iter().map(|m| m.to_string()).collect())
+}
+
+fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+// First marker on the line that stands as a whole word, with its byte column.
+fn find_marker<'a>(line: &str, markers: &'a [String]) -> Option<(usize, &'a str)> {
+    let bytes = line.as_bytes();
+    markers
+        .iter()
+        .filter_map(|m| {
+            line.match_indices(m.as_str())
+                .find(|(i, _)| {
+                    let before = *i == 0 || !is_word_byte(bytes[i - 1]);
+                    let after = bytes.get(i + m.len()).is_none_or(|b| !is_word_byte(*b));
+                    before && after
+                })
+                .map(|(i, _)| (i, m.as_str()))
+        })
+        .min_by_key(|(i, _)| *i)
+}
+
+fn scan_text(
+    path: &str,
+    text: &str,
+    row: usize,
+    col: usize,
+    markers: &[String],
+    out: &mut Vec<TodoItem>,
+) {
+    for (i, line) in text.split('\n').enumerate() {
+        if let Some((pos, marker)) = find_marker(line, markers) {
+            let rest = line[pos + marker.len()..]
+                .trim_start_matches(|c: char| c == ':' || c.is_whitespace());
+            let rest = rest.trim_end_matches(|c: char| c == '/' || c == '*' || c.is_whitespace());
+            out.push(TodoItem {
+                path: path.to_string(),
+                line: row + i,
+                col: pos + if i == 0 { col } else { 0 },
+                marker: marker.to_string(),
+                text: rest.trim_end_matches("-->").trim().to_string(),
+            });
+        }
+    }
+}
+
+fn scan_comments(
+    node: Node<'_>,
+    source: &str,
+    path: &str,
+    markers: &[String],
+    out: &mut Vec<TodoItem>,
+) {
+    if node.kind().contains("comment") {
+        if let Ok(text) = node.utf8_text(source.as_bytes()) {
+            let start = node.start_position();
+            scan_text(path, text, start.row, start.column, markers, out);
+        }
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        scan_comments(child, source, path, markers, out);
+    }
+}
+
+// Only comment nodes are scanned when a grammar exists, so markers inside strings are skipped.
+fn todos_in_source(path: &str, language: &str, source: &str, markers: &[String]) -> Vec<TodoItem> {
+    let mut out = Vec::new();
+    match parse_text(language, source) {
+        Some(tree) => scan_comments(tree.root_node(), source, path, markers, &mut out),
+        None => scan_text(path, source, 0, 0, markers, &mut out),
+    }
+    out
+}
+
+fn walk(root: &Path, markers: &[String], out: &mut Vec<TodoItem>) {
+    walk_files(root, |path| {
+        if let Ok(source) = read_text_file(path) {
+            let language = detect_language_from_extension(&path.to_path_buf());
+            out.extend(todos_in_source(
+                &path.to_string_lossy(),
+                &language,
+                &source,
+                markers,
+            ));
+        }
+        true
+    });
+}
+
+#[tauri::command]
+pub fn list_todos(state: State<'_, EditorState>) -> Result<Vec<TodoItem>, String> {
+    let guard = state.0.lock().unwrap();
+    let file = guard.active().ok_or("no file opened")?;
+    Ok(todos_in_source(
+        &file.path.to_string_lossy(),
+        &file.language,
+        &file.lines.join("\n"),
+        &markers(),
+    ))
+}
+
+#[tauri::command]
+pub async fn list_todos_in_directory(
+    app: AppHandle,
+    root: String,
+) -> Result<Vec<TodoItem>, String> {
+    let root_pb = resolve_path(&app, &root)?;
+    if !root_pb.is_dir() {
+        return Err("path is not a directory".into());
+    }
+    tokio::task::spawn_blocking(move || {
+        let mut out = Vec::new();
+        walk(&root_pb, &markers(), &mut out);
+        out.sort_by(|a, b| a.path.cmp(&b.path).then(a.line.cmp(&b.line)));
+        out
+    })
+    .await
+    .map_err(|e| e.to_string())
+}